@@ -5,6 +5,9 @@ pub struct ChooseGameComponent;
 
 #[derive(Component)]
 pub struct ChooseGameButtonComponent {
+    /// Read by the tests to find the buttons
+    #[allow(dead_code)]
     pub name: &'static str,
-    pub board: &'static str,
+    pub index: usize,
+    pub unlocked: bool,
 }
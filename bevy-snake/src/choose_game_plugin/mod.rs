@@ -8,7 +8,11 @@ use bevy::{
     ui::{AlignItems, BackgroundColor, Interaction, JustifyContent, Style, UiRect, Val},
 };
 
-use crate::{events::GameChosen, resources::Assets, AppState, LEVELS};
+use crate::{
+    events::GameChosen,
+    resources::{Assets, CampaignResource},
+    AppState,
+};
 
 #[cfg(not(test))]
 mod components;
@@ -52,7 +56,7 @@ fn remove_choose_game(
     commands.entity(entity).despawn_recursive();
 }
 
-fn draw_choose_game(mut commands: Commands, assets: Res<Assets>) {
+fn draw_choose_game(mut commands: Commands, assets: Res<Assets>, campaign: Res<CampaignResource>) {
     let font = assets.font.clone();
 
     commands
@@ -66,10 +70,19 @@ fn draw_choose_game(mut commands: Commands, assets: Res<Assets>) {
         })
         .insert(ChooseGameComponent)
         .with_children(|parent| {
-            let num = LEVELS.len();
+            let levels = campaign.0.levels();
+            let num = levels.len();
             let percent = 100. / num as f32;
 
-            for (name, board) in LEVELS {
+            for (index, level) in levels.iter().enumerate() {
+                let name = level.name;
+                let unlocked = campaign.0.is_unlocked(index);
+                let (button_color, label) = if unlocked {
+                    (assets.normal_button_color, name.to_owned())
+                } else {
+                    (assets.locked_button_color, format!("{} (locked)", name))
+                };
+
                 parent
                     .spawn(NodeBundle {
                         style: Style {
@@ -93,13 +106,17 @@ fn draw_choose_game(mut commands: Commands, assets: Res<Assets>) {
                                     align_items: AlignItems::Center,
                                     ..default()
                                 },
-                                background_color: assets.normal_button_color.into(),
+                                background_color: button_color.into(),
                                 ..default()
                             })
-                            .insert(ChooseGameButtonComponent { name, board })
+                            .insert(ChooseGameButtonComponent {
+                                name,
+                                index,
+                                unlocked,
+                            })
                             .with_children(|parent| {
                                 parent.spawn(TextBundle::from_section(
-                                    name,
+                                    label,
                                     TextStyle {
                                         font: font.clone(),
                                         font_size: 30.0,
@@ -126,12 +143,16 @@ fn handle_choose_game(
     >,
 ) {
     for (interaction, mut color, choose_game_component) in &mut interaction_query {
+        // Locked levels cannot be chosen
+        if !choose_game_component.unlocked {
+            continue;
+        }
+
         match *interaction {
             Interaction::Clicked => {
                 *color = assets.pressed_button_color.into();
 
-                let board = choose_game_component.board;
-                game_chosen_writer.send(GameChosen(board));
+                game_chosen_writer.send(GameChosen(choose_game_component.index));
             }
             Interaction::Hovered => {
                 *color = assets.hovered_button_color.into();
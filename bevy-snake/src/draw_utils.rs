@@ -1,5 +1,5 @@
 use bevy::prelude::{Commands, Mut, Resource, Transform, Vec2, Vec3};
-use snake::Point;
+use snake::{topology::Topology, Game, Point};
 
 use crate::{
     play_plugin::components::*,
@@ -11,22 +11,69 @@ const WALL_Z: f32 = 1.;
 const SNAKE_Z: f32 = 2.;
 const FOOD_Z: f32 = 2.;
 
+/// Distance between the centers of two rows of hexagons, in cells
+const HEX_ROW_HEIGHT: f32 = 0.866_025_4;
+
+/// How the cells are placed on the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    Square,
+    /// Odd rows are shifted half a cell to the right
+    Hex,
+}
+
+impl Layout {
+    pub fn of<T: Topology>(_game: &Game<T>) -> Self {
+        if T::HEXAGONAL {
+            Layout::Hex
+        } else {
+            Layout::Square
+        }
+    }
+
+    /// The size of a `dim` board, in cells
+    pub fn size_in_cells(self, dim: (usize, usize)) -> Vec2 {
+        let (w, h) = (dim.0 as f32, dim.1 as f32);
+        match self {
+            Layout::Square => Vec2::new(w, h),
+            Layout::Hex => Vec2::new(w + 0.5, (h - 1.) * HEX_ROW_HEIGHT + 1.),
+        }
+    }
+}
+
 #[derive(Resource)]
 pub struct DrawConfigurationResource {
     pub cell_size: f32,
     pub dim: (usize, usize),
+    pub layout: Layout,
 }
 
 impl DrawConfigurationResource {
-    pub fn spawn_background(&self, commands: &mut Commands, bundles: &Assets) {
-        let mut background = bundles.background_tile.clone();
-        background.transform.translation.z = BACKGROUND_Z;
-        background.sprite.custom_size = Some(Vec2::new(
-            self.cell_size * self.dim.0 as f32,
-            self.cell_size * self.dim.1 as f32,
-        ));
-
-        commands.spawn(background);
+    pub fn spawn_background(&self, commands: &mut Commands, bundles: &Assets, voids: &[Point]) {
+        if voids.is_empty() {
+            let mut background = bundles.background_tile.clone();
+            background.transform.translation.z = BACKGROUND_Z;
+            background.sprite.custom_size = Some(self.board_size());
+
+            commands.spawn(background).insert(BackgroundComponent);
+            return;
+        }
+
+        // One tile per cell so the void cells are left empty
+        for y in 0..self.dim.1 {
+            for x in 0..self.dim.0 {
+                let position = Point { x, y };
+                if voids.contains(&position) {
+                    continue;
+                }
+
+                let mut background = bundles.background_tile.clone();
+                background.transform.translation = self.get_translation(&position, BACKGROUND_Z);
+                background.sprite.custom_size = Some(Vec2::new(self.cell_size, self.cell_size));
+
+                commands.spawn(background).insert(BackgroundComponent);
+            }
+        }
     }
 
     pub fn spawn(
@@ -74,11 +121,64 @@ impl DrawConfigurationResource {
     }
 
     pub fn get_translation(&self, point: &Point, z: f32) -> Vec3 {
-        let x = point.x as f32 * self.cell_size + self.cell_size / 2.
-            - (self.cell_size * self.dim.0 as f32) / 2.;
-        let y = point.y as f32 * self.cell_size + self.cell_size / 2.
-            - (self.cell_size * self.dim.1 as f32) / 2.;
+        let board_size = self.board_size();
+
+        let (x, y) = match self.layout {
+            Layout::Square => (
+                point.x as f32 * self.cell_size,
+                point.y as f32 * self.cell_size,
+            ),
+            Layout::Hex => {
+                let shift = if point.y % 2 == 1 {
+                    self.cell_size / 2.
+                } else {
+                    0.
+                };
+                (
+                    point.x as f32 * self.cell_size + shift,
+                    point.y as f32 * self.cell_size * HEX_ROW_HEIGHT,
+                )
+            }
+        };
+
+        let x = x + self.cell_size / 2. - board_size.x / 2.;
+        let y = y + self.cell_size / 2. - board_size.y / 2.;
 
         Vec3::new(x, y, z)
     }
+
+    /// The size of the whole board on the screen
+    pub fn board_size(&self) -> Vec2 {
+        self.layout.size_in_cells(self.dim) * self.cell_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Vec2;
+    use snake::Point;
+
+    use super::{DrawConfigurationResource, Layout, HEX_ROW_HEIGHT};
+
+    #[test]
+    fn test_hex_translation() {
+        let configuration = DrawConfigurationResource {
+            cell_size: 10.,
+            dim: (4, 2),
+            layout: Layout::Hex,
+        };
+        assert!(configuration
+            .board_size()
+            .abs_diff_eq(Vec2::new(45., 10. * HEX_ROW_HEIGHT + 10.), 1e-4));
+
+        let translation = |x, y| configuration.get_translation(&Point { x, y }, 0.);
+        let bottom_left = translation(0, 0);
+        assert_eq!(bottom_left.x, 5. - 22.5);
+
+        // Odd rows are shifted by half a cell, rows are closer than cells
+        let above = translation(0, 1);
+        assert_eq!(above.x - bottom_left.x, 5.);
+        assert!((above.y - bottom_left.y - 10. * HEX_ROW_HEIGHT).abs() < 1e-4);
+        assert_eq!(translation(1, 0).x - bottom_left.x, 10.);
+    }
 }
@@ -1,5 +1,9 @@
+/// Force a tick of the game without waiting for its period
 pub struct GameTick;
 
-pub struct GameChosen(pub &'static str);
+/// The index of the chosen level in the campaign
+pub struct GameChosen(pub usize);
+
+pub struct LevelCompleted;
 
 pub struct GameOver(pub &'static str);
@@ -2,3 +2,6 @@ use bevy::prelude::Component;
 
 #[derive(Component)]
 pub struct QuitComponent;
+
+#[derive(Component)]
+pub struct RecordComponent;
@@ -1,8 +1,8 @@
 use bevy::{
     app::AppExit,
     prelude::{
-        default, BuildChildren, Button, ButtonBundle, Changed, Color, Commands, EventReader,
-        EventWriter, NodeBundle, Plugin, Query, Res, ResMut, State, SystemSet, TextBundle, With,
+        default, BuildChildren, Button, ButtonBundle, Changed, Commands, EventReader, EventWriter,
+        NodeBundle, Plugin, Query, Res, ResMut, State, SystemSet, TextBundle, With,
     },
     text::TextStyle,
     ui::{
@@ -10,7 +10,13 @@ use bevy::{
     },
 };
 
-use crate::{events::GameOver, resources::Assets, AppState};
+use crate::{
+    events::GameOver,
+    resources::{
+        Assets, CampaignResource, CurrentLevelResource, GameResource, ScoreStorageResource,
+    },
+    AppState,
+};
 
 #[cfg(not(test))]
 mod components;
@@ -52,6 +58,10 @@ fn show_game_over_screen(
     mut commands: Commands,
     app_state: Res<State<AppState>>,
     assets: Res<Assets>,
+    game: Res<GameResource>,
+    campaign: Res<CampaignResource>,
+    current_level: Res<CurrentLevelResource>,
+    mut score_storage: ResMut<ScoreStorageResource>,
 ) {
     let font = assets.font.clone();
 
@@ -62,6 +72,13 @@ fn show_game_over_screen(
         ),
     };
 
+    let level = campaign.0.level(current_level.0).unwrap().name;
+    let record = match score_storage.submit(level, &game.0) {
+        Some(0) => "New record!".to_owned(),
+        Some(rank) => format!("High score #{}", rank + 1),
+        None => String::new(),
+    };
+
     commands
         .spawn(NodeBundle {
             style: Style {
@@ -83,10 +100,21 @@ fn show_game_over_screen(
                 TextStyle {
                     font: font.clone(),
                     font_size: 30.0,
-                    color: Color::WHITE,
+                    color: assets.text_color,
                 },
             ));
 
+            parent
+                .spawn(TextBundle::from_section(
+                    record,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 30.0,
+                        color: assets.text_color,
+                    },
+                ))
+                .insert(RecordComponent);
+
             parent
                 .spawn(ButtonBundle {
                     style: Style {
@@ -5,8 +5,9 @@ use choose_game_plugin::ChooseGamePlugin;
 
 use game_over_plugin::GameOverPlugin;
 use play_plugin::SnakePlugin;
-use resources::Assets;
-use snake::{SNAKE_1, SNAKE_2};
+use resources::{
+    Assets, CampaignResource, HexBoardsResource, ScoreStorageResource, ViewportResource, HEX_ENV,
+};
 
 mod choose_game_plugin;
 mod draw_utils;
@@ -14,12 +15,17 @@ mod events;
 mod game_over_plugin;
 mod play_plugin;
 mod resources;
+#[cfg(not(target_arch = "wasm32"))]
+mod spectate_plugin;
 
 #[derive(Debug, Clone)]
 enum AppState {
     ChooseGame,
     Play,
     GameOver(&'static str),
+    /// Watch a game streamed by someone else
+    #[cfg(not(target_arch = "wasm32"))]
+    Spectate,
 }
 
 impl PartialEq for AppState {
@@ -34,8 +40,6 @@ impl Hash for AppState {
     }
 }
 
-static LEVELS: [(&str, &str); 2] = [("snake1", SNAKE_1), ("snake2", SNAKE_2)];
-
 pub struct MainPlugin;
 
 impl Plugin for MainPlugin {
@@ -45,14 +49,34 @@ impl Plugin for MainPlugin {
             .insert_resource(ClearColor(Color::rgb(0.2, 0.2, 0.2)))
             // Images used for drawing stuff
             .init_resource::<Assets>()
+            // Levels and progress
+            .init_resource::<CampaignResource>()
+            // High scores
+            .init_resource::<ScoreStorageResource>()
+            // Scrolling on big boards
+            .init_resource::<ViewportResource>()
+            // Square or hexagonal boards
+            .insert_resource(HexBoardsResource(std::env::var(HEX_ENV).is_ok()))
             // Other plugins...
             .add_plugin(ChooseGamePlugin)
             .add_plugin(SnakePlugin)
             .add_plugin(GameOverPlugin)
-            .add_state(AppState::ChooseGame);
+            .add_state(initial_state());
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_plugin(spectate_plugin::SpectatePlugin);
     }
 }
 
+fn initial_state() -> AppState {
+    #[cfg(not(target_arch = "wasm32"))]
+    if std::env::var(spectate_plugin::SPECTATE_ENV).is_ok() {
+        return AppState::Spectate;
+    }
+
+    AppState::ChooseGame
+}
+
 fn add_camera(mut commands: Commands) {
     let camera = Camera2dBundle::default();
     commands.spawn(camera);
@@ -77,7 +101,7 @@ mod tests {
     use crate::{
         choose_game_plugin::components::*,
         events::GameTick,
-        game_over_plugin::components::QuitComponent,
+        game_over_plugin::components::{QuitComponent, RecordComponent},
         play_plugin::components::ScoreComponent,
         resources::{ScoreResource, SnakeResource},
         MainPlugin,
@@ -129,6 +153,10 @@ mod tests {
             true,
         );
 
+        // The first game is always a record
+        let records = get_entities_with::<&Text, &RecordComponent>(&mut app);
+        assert_eq!(records[0].sections[0].value, "New record!");
+
         let buttons = get_entities_with::<(Entity, &QuitComponent), &Button>(&mut app);
         let (quit, _) = buttons.into_iter().next().unwrap();
         click_on(&mut app, quit);
@@ -200,23 +228,23 @@ mod tests {
         app.world.clear_trackers();
 
         app.add_plugin(CorePlugin::default())
-            .add_plugin(TimePlugin::default())
-            .add_plugin(TransformPlugin::default())
-            .add_plugin(HierarchyPlugin::default())
-            .add_plugin(InputPlugin::default())
+            .add_plugin(TimePlugin)
+            .add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            .add_plugin(InputPlugin)
             .add_plugin(WindowPlugin {
                 add_primary_window: true,
                 ..default() // exit_on_close: false,
             })
             .add_plugin(AssetPlugin::default())
-            .add_plugin(ScenePlugin::default())
-            .add_plugin(RenderPlugin::default())
-            .add_plugin(CorePipelinePlugin::default())
-            .add_plugin(TextPlugin::default())
-            .add_plugin(UiPlugin::default())
+            .add_plugin(ScenePlugin)
+            .add_plugin(RenderPlugin)
+            .add_plugin(CorePipelinePlugin)
+            .add_plugin(TextPlugin)
+            .add_plugin(UiPlugin)
             .add_plugin(ImagePlugin::default())
-            .add_plugin(PbrPlugin::default())
-            .add_plugin(SpritePlugin::default());
+            .add_plugin(PbrPlugin)
+            .add_plugin(SpritePlugin);
 
         let mut windows = app.world.resource_mut::<Windows>();
         windows.add(Window::new(
@@ -0,0 +1,152 @@
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    math::{UVec2, Vec3Swizzles},
+    prelude::{
+        default, Camera, Camera2d, Camera2dBundle, Commands, OrthographicProjection, Query, Res,
+        Transform, UiCameraConfig, Vec2, With, Without,
+    },
+    render::camera::Viewport,
+    time::Time,
+    window::{Window, Windows},
+};
+
+use crate::{draw_utils::DrawConfigurationResource, resources::ViewportResource};
+
+use super::components::{MinimapComponent, SnakeHeadComponent};
+
+/// How fast the camera reaches the head: higher is snappier
+const FOLLOW_SPEED: f32 = 8.;
+
+/// Size of the minimap, relative to the shortest side of the window
+const MINIMAP_RATIO: f32 = 0.25;
+const MINIMAP_MARGIN: u32 = 10;
+
+/// Cells are as big as the window allows, but never smaller than the
+/// minimum of the viewport: bigger boards scroll. `size` is the size of
+/// the board in cells.
+pub fn calculate_cell_size(size: Vec2, window: &Window, viewport: &ViewportResource) -> f32 {
+    let height = window.height();
+    let width = window.width();
+    let fit = (height / size.y).min(width / size.x);
+    fit.max(viewport.min_cell_size)
+}
+
+fn scrolls(drawing_configuration: &DrawConfigurationResource, window: &Window) -> bool {
+    let board_size = drawing_configuration.board_size();
+    board_size.x > window.width() || board_size.y > window.height()
+}
+
+/// Where the camera should look to have `target` in the middle, without
+/// showing what is outside the board
+fn clamp_to_board(target: Vec2, board_size: Vec2, window: &Window) -> Vec2 {
+    let clamp = |v: f32, board: f32, view: f32| {
+        let max = ((board - view) / 2.).max(0.);
+        v.clamp(-max, max)
+    };
+    Vec2::new(
+        clamp(target.x, board_size.x, window.width()),
+        clamp(target.y, board_size.y, window.height()),
+    )
+}
+
+pub fn reset_camera(
+    mut camera_query: Query<&mut Transform, (With<Camera2d>, Without<MinimapComponent>)>,
+) {
+    for mut transform in camera_query.iter_mut() {
+        transform.translation.x = 0.;
+        transform.translation.y = 0.;
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn follow_head(
+    time: Res<Time>,
+    windows: Res<Windows>,
+    drawing_configuration: Res<DrawConfigurationResource>,
+    head_query: Query<&Transform, With<SnakeHeadComponent>>,
+    mut camera_query: Query<
+        &mut Transform,
+        (
+            With<Camera2d>,
+            Without<MinimapComponent>,
+            Without<SnakeHeadComponent>,
+        ),
+    >,
+) {
+    let window = windows.primary();
+    if !scrolls(&drawing_configuration, window) {
+        return;
+    }
+
+    let head = match head_query.get_single() {
+        Ok(head) => head,
+        Err(_) => return,
+    };
+    let target = clamp_to_board(
+        head.translation.xy(),
+        drawing_configuration.board_size(),
+        window,
+    );
+
+    for mut transform in camera_query.iter_mut() {
+        let current = transform.translation.xy();
+        let distance = (target - current).abs();
+
+        // The head went through an edge of a wrapping board:
+        // cut to the other side instead of panning across the whole board
+        let position = if distance.x > window.width() / 2. || distance.y > window.height() / 2. {
+            target
+        } else {
+            let t = 1. - (-FOLLOW_SPEED * time.delta_seconds()).exp();
+            current.lerp(target, t)
+        };
+
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+pub fn spawn_minimap(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    viewport: Res<ViewportResource>,
+    drawing_configuration: Res<DrawConfigurationResource>,
+) {
+    let window = windows.primary();
+    if !viewport.minimap || !scrolls(&drawing_configuration, window) {
+        return;
+    }
+
+    let physical_width = window.physical_width();
+    let physical_height = window.physical_height();
+    let side = (physical_width.min(physical_height) as f32 * MINIMAP_RATIO) as u32;
+
+    // The whole board fits in the minimap
+    let board_size = drawing_configuration.board_size();
+    let scale = board_size.max_element() * window.scale_factor() as f32 / side as f32;
+
+    commands
+        .spawn(Camera2dBundle {
+            camera: Camera {
+                // Drawn over the board
+                priority: 1,
+                viewport: Some(Viewport {
+                    physical_position: UVec2::new(
+                        physical_width.saturating_sub(side + MINIMAP_MARGIN),
+                        MINIMAP_MARGIN,
+                    ),
+                    physical_size: UVec2::new(side, side),
+                    ..default()
+                }),
+                ..default()
+            },
+            camera_2d: Camera2d {
+                // Clearing would erase the board drawn by the main camera
+                clear_color: ClearColorConfig::None,
+            },
+            projection: OrthographicProjection { scale, ..default() },
+            ..default()
+        })
+        .insert(UiCameraConfig { show_ui: false })
+        .insert(MinimapComponent);
+}
@@ -14,3 +14,10 @@ pub struct FoodComponent;
 
 #[derive(Component)]
 pub struct ScoreComponent;
+
+#[derive(Component)]
+pub struct BackgroundComponent;
+
+/// Camera showing the whole board in a corner
+#[derive(Component)]
+pub struct MinimapComponent;
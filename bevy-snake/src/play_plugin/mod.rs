@@ -2,24 +2,26 @@ use bevy::{
     ecs::{schedule::ShouldRun, system::SystemState},
     input::keyboard::KeyboardInput,
     prelude::{
-        Commands, Entity, EventReader, EventWriter, KeyCode, Plugin, Query, Res, ResMut, State,
-        SystemSet, TextBundle, Transform, With, Without, World,
+        Commands, Entity, EventReader, EventWriter, IntoSystemDescriptor, KeyCode, Or, Plugin,
+        Query, Res, ResMut, State, SystemSet, TextBundle, Transform, With, Without, World,
     },
     text::{Text, TextStyle},
-    time::{Time, Timer, TimerMode},
-    window::{Window, Windows},
+    time::Time,
+    window::Windows,
 };
-use snake::{Direction, Game};
+use snake::{topology::HexDirection, Direction};
 
 use crate::{
     draw_utils::DrawConfigurationResource,
-    events::{GameChosen, GameOver, GameTick},
+    events::{GameChosen, GameOver, GameTick, LevelCompleted},
     resources::*,
     AppState,
 };
 
+pub mod camera;
 pub mod components;
 
+use camera::*;
 use components::*;
 
 pub struct SnakePlugin;
@@ -36,6 +38,7 @@ impl Plugin for SnakePlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.add_event::<GameTick>()
             .add_event::<GameOver>()
+            .add_event::<LevelCompleted>()
             // Outside Play
             .add_system_set(
                 SystemSet::new()
@@ -46,8 +49,11 @@ impl Plugin for SnakePlugin {
             .add_system_set(
                 SystemSet::on_enter(AppState::Play)
                     .with_system(add_all_resources)
+                    // remove the board of the previous level
+                    .with_system(clear_board)
                     // init draw
-                    .with_system(init_draw),
+                    .with_system(init_draw)
+                    .with_system(spawn_minimap),
             )
             // Run Play
             .add_system_set(
@@ -61,13 +67,13 @@ impl Plugin for SnakePlugin {
                     .with_system(update_food)
                     .with_system(update_score)
                     .with_system(handle_keyboard_input)
-                    // Run tick
-                    .with_system(send_game_tick),
+                    // Scroll big boards
+                    .with_system(follow_head.after(update_snake))
+                    // Go to the next level
+                    .with_system(wait_for_level_completed_event),
             )
-            // Go out Play
-            .add_system_set(
-                SystemSet::on_exit(AppState::Play).with_system(stop_timer_on_game_over),
-            );
+            // Exit from Play
+            .add_system_set(SystemSet::on_exit(AppState::Play).with_system(reset_camera));
     }
 }
 
@@ -75,47 +81,115 @@ fn wait_for_game_chosen_event(world: &mut World) {
     let mut initial_state: SystemState<EventReader<GameChosen>> = SystemState::new(world);
     let mut event_reader = initial_state.get_mut(world);
 
-    let game_board = match event_reader.iter().last().map(|e| &e.0) {
-        Some(game_board) => game_board,
+    let level = match event_reader.iter().last().map(|e| e.0) {
+        Some(level) => level,
         None => return,
     };
 
-    let game: Game = game_board.parse().unwrap();
+    start_level(world, level);
+
+    let mut app_state = world.resource_mut::<State<AppState>>();
+    app_state.set(AppState::Play).unwrap();
+}
+
+fn wait_for_level_completed_event(
+    world: &mut World,
+    // Kept between runs so the same event is not read twice
+    event_state: &mut SystemState<EventReader<LevelCompleted>>,
+) {
+    let mut event_reader = event_state.get_mut(world);
+
+    if event_reader.iter().count() == 0 {
+        return;
+    }
+
+    let current_level = world.resource::<CurrentLevelResource>().0;
+    let next_level = world
+        .resource::<CampaignResource>()
+        .0
+        .next_level(current_level);
+
+    match next_level {
+        Some(level) => {
+            start_level(world, level);
+
+            // Exit and enter again in Play to draw the new board
+            let mut app_state = world.resource_mut::<State<AppState>>();
+            app_state.restart().unwrap();
+        }
+        None => {
+            world.send_event(GameOver("Campaign completed"));
+        }
+    }
+}
+
+fn start_level(world: &mut World, level: usize) {
+    let campaign = &world.resource::<CampaignResource>().0;
+    let hex = world.resource::<HexBoardsResource>().0;
+    // The levels of the campaign have even heights: they fit hex boards too
+    let mut game = AnyGame::parse(campaign.level(level).unwrap().board, hex).unwrap();
+
+    let game_events = GameEventsResource::default();
+    game.add_observer(game_events.clone());
 
     world
         // Shadow resources
         .insert_resource(GameResource(game));
-
-    let mut app_state = world.resource_mut::<State<AppState>>();
-    app_state.set(AppState::Play).unwrap();
+    world.insert_resource(game_events);
+    world.insert_resource(CurrentLevelResource(level));
 }
 
 fn add_all_resources(world: &mut World) {
     let game = &world.resource::<GameResource>().0;
 
     let dim = game.dim();
-    let cell_size = calculate_cell_size(dim, world.resource::<Windows>().primary());
+    let layout = game.layout();
+    let cell_size = calculate_cell_size(
+        layout.size_in_cells(dim),
+        world.resource::<Windows>().primary(),
+        world.resource::<ViewportResource>(),
+    );
 
     let snapshot = game.last_snapshot();
-    let duration = snapshot.period_duration;
 
     let walls = game.walls();
+    let voids = game.voids();
+    let direction = game.direction();
 
     // Keep track cell size
-    world.insert_resource(DrawConfigurationResource { cell_size, dim });
+    world.insert_resource(DrawConfigurationResource {
+        cell_size,
+        dim,
+        layout,
+    });
     world
         // Shadow resources
         .insert_resource(WallsResource(Some(walls)));
+    world.insert_resource(VoidsResource(voids));
     world.insert_resource(ScoreResource(snapshot.score));
     world.insert_resource(SnakeResource(snapshot.snake));
     world.insert_resource(FoodPositionResource(snapshot.food_position));
-    world.insert_resource(CurrentDirection(Direction::Up));
-    world
-        // Timer
-        .insert_resource(GameTimerResource(Timer::new(
-            duration,
-            TimerMode::Repeating,
-        )));
+    world.insert_resource(CurrentDirection(direction));
+}
+
+#[allow(clippy::type_complexity)]
+fn clear_board(
+    mut commands: Commands,
+    board_query: Query<
+        Entity,
+        Or<(
+            With<BackgroundComponent>,
+            With<SnakeHeadComponent>,
+            With<SnakeBodyComponent>,
+            With<FoodComponent>,
+            With<ScoreComponent>,
+            With<MinimapComponent>,
+        )>,
+    >,
+) {
+    for entity in board_query.iter() {
+        commands.entity(entity).despawn();
+    }
 }
 
 fn init_draw(
@@ -125,13 +199,14 @@ fn init_draw(
     assets: Res<Assets>,
     drawing_configuration: Res<DrawConfigurationResource>,
     score: Res<ScoreResource>,
+    voids: Res<VoidsResource>,
 ) {
     let mut snake_iter = snake.0.iter();
 
     let head = snake_iter.next().unwrap();
 
     // Background
-    drawing_configuration.spawn_background(&mut commands, &assets);
+    drawing_configuration.spawn_background(&mut commands, &assets, &voids.0);
 
     // Head
     drawing_configuration.spawn(&mut commands, &assets, BundleType::SnakeHead, head);
@@ -185,55 +260,55 @@ fn draw_walls(
     };
 }
 
-fn send_game_tick(
-    time: Res<Time>,
-    mut game_timers: ResMut<GameTimerResource>,
-    mut tick_event_writer: EventWriter<GameTick>,
-) {
-    let game_timers = &mut game_timers.0;
-    if !game_timers.tick(time.delta()).finished() {
-        return;
-    }
-
-    tick_event_writer.send(GameTick);
-}
-
 #[allow(clippy::too_many_arguments)]
 fn tick(
+    time: Res<Time>,
     mut tick_event: EventReader<GameTick>,
     mut game_over_writer: EventWriter<GameOver>,
+    mut level_completed_writer: EventWriter<LevelCompleted>,
+    mut campaign: ResMut<CampaignResource>,
+    current_level: Res<CurrentLevelResource>,
     mut game: ResMut<GameResource>,
+    game_events: Res<GameEventsResource>,
     mut score: ResMut<ScoreResource>,
     mut snake: ResMut<SnakeResource>,
     mut food_position: ResMut<FoodPositionResource>,
     current_direction: Res<CurrentDirection>,
-    mut game_timers: ResMut<GameTimerResource>,
 ) {
-    if tick_event.iter().count() == 0 {
-        return;
-    }
+    game.0.set_direction(current_direction.0);
 
-    game.0.tick(current_direction.0);
+    // The game runs as many ticks as the elapsed time allows
+    let mut ticks = game.0.advance(time.delta());
 
-    let snapshot = game.0.last_snapshot();
+    // `GameTick` forces a tick without waiting
+    if tick_event.iter().count() > 0 && !game.0.is_over() {
+        game.0.tick(current_direction.0);
+        ticks += 1;
+    }
 
-    // game over
-    if let Some(reason) = snapshot.get_game_over_reason() {
-        game_over_writer.send(GameOver(reason));
+    if ticks == 0 {
         return;
     }
 
     // Update resources
-    snake.0 = snapshot.snake;
-    if score.0 != snapshot.score {
-        score.0 = snapshot.score;
-    }
-    if snapshot.food_position != food_position.0 {
-        food_position.0 = snapshot.food_position;
+    for event in game_events.take() {
+        match event {
+            SnakeEvent::Death(reason) => {
+                game_over_writer.send(GameOver(reason));
+                return;
+            }
+            SnakeEvent::FoodEaten => score.0 += 1,
+            SnakeEvent::FoodSpawned(position) => food_position.0 = position,
+        }
     }
-    if game_timers.0.duration() != snapshot.period_duration {
-        game_timers.0.set_duration(snapshot.period_duration);
+
+    let snapshot = game.0.last_snapshot();
+
+    if campaign.0.record(current_level.0, &snapshot) {
+        level_completed_writer.send(LevelCompleted);
     }
+
+    snake.0 = snapshot.snake;
 }
 
 fn update_food(
@@ -286,7 +361,7 @@ fn update_snake(
     // HEAD
     // We are sure than head always exists
     let transform = head_snake_query.single_mut();
-    let position = snake.0.get(0).unwrap();
+    let position = snake.0.first().unwrap();
 
     drawing_configuration.translate(BundleType::SnakeHead, transform, position);
 
@@ -316,33 +391,48 @@ fn update_snake(
     }
 }
 
+/// The arrows on square boards. Hex boards take Q, E, A, D, Z and C, like
+/// the six keys around S, and the left and right arrows.
 fn handle_keyboard_input(
     mut keyboard_input_events: EventReader<KeyboardInput>,
     mut current_direction: ResMut<CurrentDirection>,
 ) {
+    let hex = matches!(current_direction.0, AnyDirection::Hex(_));
     let direction = keyboard_input_events
         .iter()
         .filter_map(|ki| ki.key_code)
-        .filter_map(|kc| match kc {
-            KeyCode::Up => Some(Direction::Up),
-            KeyCode::Down => Some(Direction::Down),
-            KeyCode::Left => Some(Direction::Left),
-            KeyCode::Right => Some(Direction::Right),
-            _ => None,
+        .filter_map(|kc| {
+            if hex {
+                hex_direction(kc).map(AnyDirection::Hex)
+            } else {
+                square_direction(kc).map(AnyDirection::Square)
+            }
         })
-        .last();
+        .next_back();
 
     if let Some(direction) = direction {
         current_direction.0 = direction;
     }
 }
 
-fn stop_timer_on_game_over(mut game_timers: ResMut<GameTimerResource>) {
-    game_timers.0.pause();
+fn square_direction(key_code: KeyCode) -> Option<Direction> {
+    match key_code {
+        KeyCode::Up => Some(Direction::Up),
+        KeyCode::Down => Some(Direction::Down),
+        KeyCode::Left => Some(Direction::Left),
+        KeyCode::Right => Some(Direction::Right),
+        _ => None,
+    }
 }
 
-fn calculate_cell_size(dim: (usize, usize), window: &Window) -> f32 {
-    let height = window.height();
-    let width = window.width();
-    (height / dim.1 as f32).min(width / dim.0 as f32)
+fn hex_direction(key_code: KeyCode) -> Option<HexDirection> {
+    match key_code {
+        KeyCode::Q => Some(HexDirection::UpLeft),
+        KeyCode::E => Some(HexDirection::UpRight),
+        KeyCode::A | KeyCode::Left => Some(HexDirection::Left),
+        KeyCode::D | KeyCode::Right => Some(HexDirection::Right),
+        KeyCode::Z => Some(HexDirection::DownLeft),
+        KeyCode::C => Some(HexDirection::DownRight),
+        _ => None,
+    }
 }
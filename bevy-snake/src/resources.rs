@@ -1,17 +1,166 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
 use bevy::{
     prelude::{AssetServer, Color, FromWorld, Handle, Resource},
     sprite::{Sprite, SpriteBundle},
     text::Font,
-    time::Timer,
 };
-use snake::{Direction, Game, Point};
+use snake::{
+    campaign::Campaign,
+    observer::GameObserver,
+    replay::Replay,
+    scores::{ScoreEntry, ScoreStorage},
+    topology::{Hex, HexDirection},
+    Direction, Game, Point, Snapshot,
+};
+
+use crate::draw_utils::Layout;
+
+/// Play the levels on hexagonal boards, when set
+pub const HEX_ENV: &str = "SNAKE_HEX";
+
+/// The levels are played on hexagonal boards, see [`HEX_ENV`]
+#[derive(Resource, Default)]
+pub struct HexBoardsResource(pub bool);
+
+/// A game on a square or a hexagonal board
+pub enum AnyGame {
+    Square(Game),
+    Hex(Game<Hex>),
+}
+
+/// A direction of an [`AnyGame`]: the one of its board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnyDirection {
+    Square(Direction),
+    Hex(HexDirection),
+}
+
+impl AnyGame {
+    pub fn parse(level: &str, hex: bool) -> Result<Self, String> {
+        if hex {
+            level.parse().map(AnyGame::Hex)
+        } else {
+            level.parse().map(AnyGame::Square)
+        }
+    }
+
+    pub fn layout(&self) -> Layout {
+        match self {
+            AnyGame::Square(game) => Layout::of(game),
+            AnyGame::Hex(game) => Layout::of(game),
+        }
+    }
+
+    /// The direction of the last tick
+    pub fn direction(&self) -> AnyDirection {
+        match self {
+            AnyGame::Square(game) => AnyDirection::Square(game.direction()),
+            AnyGame::Hex(game) => AnyDirection::Hex(game.direction()),
+        }
+    }
+
+    /// A direction for another board is ignored
+    pub fn set_direction(&mut self, direction: AnyDirection) {
+        match (self, direction) {
+            (AnyGame::Square(game), AnyDirection::Square(direction)) => {
+                game.set_direction(direction)
+            }
+            (AnyGame::Hex(game), AnyDirection::Hex(direction)) => game.set_direction(direction),
+            _ => {}
+        }
+    }
+
+    /// A direction for another board keeps the current one
+    pub fn tick(&mut self, direction: AnyDirection) {
+        match (self, direction) {
+            (AnyGame::Square(game), AnyDirection::Square(direction)) => game.tick(direction),
+            (AnyGame::Hex(game), AnyDirection::Hex(direction)) => game.tick(direction),
+            (AnyGame::Square(game), _) => game.tick(game.direction()),
+            (AnyGame::Hex(game), _) => game.tick(game.direction()),
+        }
+    }
+
+    /// See [`Game::advance`]. Returns the number of ticks run.
+    pub fn advance(&mut self, elapsed: Duration) -> usize {
+        match self {
+            AnyGame::Square(game) => game.advance(elapsed).len(),
+            AnyGame::Hex(game) => game.advance(elapsed).len(),
+        }
+    }
+
+    /// The number of ticks run so far
+    pub fn ticks(&self) -> usize {
+        match self {
+            AnyGame::Square(game) => game.directions().len(),
+            AnyGame::Hex(game) => game.directions().len(),
+        }
+    }
+
+    pub fn add_observer(&mut self, observer: impl GameObserver + 'static) {
+        match self {
+            AnyGame::Square(game) => game.add_observer(observer),
+            AnyGame::Hex(game) => game.add_observer(observer),
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        match self {
+            AnyGame::Square(game) => game.is_over(),
+            AnyGame::Hex(game) => game.is_over(),
+        }
+    }
+
+    pub fn last_snapshot(&self) -> Snapshot {
+        match self {
+            AnyGame::Square(game) => game.last_snapshot(),
+            AnyGame::Hex(game) => game.last_snapshot(),
+        }
+    }
+
+    pub fn dim(&self) -> (usize, usize) {
+        match self {
+            AnyGame::Square(game) => game.dim(),
+            AnyGame::Hex(game) => game.dim(),
+        }
+    }
+
+    pub fn walls(&self) -> Vec<Point> {
+        match self {
+            AnyGame::Square(game) => game.walls(),
+            AnyGame::Hex(game) => game.walls(),
+        }
+    }
+
+    pub fn voids(&self) -> Vec<Point> {
+        match self {
+            AnyGame::Square(game) => game.voids(),
+            AnyGame::Hex(game) => game.voids(),
+        }
+    }
+
+    /// Replays only have the four square directions
+    pub fn replay(&self) -> Option<Replay> {
+        match self {
+            AnyGame::Square(game) => Some(game.replay()),
+            AnyGame::Hex(_) => None,
+        }
+    }
+}
 
 #[derive(Resource)]
-pub struct GameResource(pub Game);
+pub struct GameResource(pub AnyGame);
 
 #[derive(Resource)]
 pub struct WallsResource(pub Option<Vec<Point>>);
 
+/// Cells that are not part of the board
+#[derive(Resource)]
+pub struct VoidsResource(pub Vec<Point>);
+
 #[derive(Resource)]
 pub struct ScoreResource(pub usize);
 
@@ -22,10 +171,127 @@ pub struct SnakeResource(pub Vec<Point>);
 pub struct FoodPositionResource(pub Point);
 
 #[derive(Resource)]
-pub struct GameTimerResource(pub Timer);
+pub struct CurrentDirection(pub AnyDirection);
+
+/// What the game reported during a tick
+pub enum SnakeEvent {
+    FoodEaten,
+    FoodSpawned(Point),
+    Death(&'static str),
+}
+
+/// Observer registered on the game: it keeps the events till the next tick
+/// system run
+#[derive(Resource, Clone, Default)]
+pub struct GameEventsResource(pub Arc<Mutex<Vec<SnakeEvent>>>);
+
+impl GameEventsResource {
+    pub fn take(&self) -> Vec<SnakeEvent> {
+        self.0.lock().unwrap().drain(..).collect()
+    }
+
+    fn push(&self, event: SnakeEvent) {
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+impl GameObserver for GameEventsResource {
+    fn on_food_eaten(&mut self, _position: Point) {
+        self.push(SnakeEvent::FoodEaten);
+    }
+
+    fn on_death(&mut self, reason: &'static str) {
+        self.push(SnakeEvent::Death(reason));
+    }
+
+    fn on_food_spawned(&mut self, position: Point) {
+        self.push(SnakeEvent::FoodSpawned(position));
+    }
+}
+
+/// How the board is shown when it doesn't fit in the window
+#[derive(Resource)]
+pub struct ViewportResource {
+    /// Cells are never drawn smaller than this: the camera scrolls instead
+    pub min_cell_size: f32,
+    /// Show the whole board in a corner while scrolling
+    pub minimap: bool,
+}
+
+impl Default for ViewportResource {
+    fn default() -> Self {
+        Self {
+            min_cell_size: 16.,
+            minimap: true,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct CampaignResource(pub Campaign);
+
+#[derive(Resource)]
+pub struct CurrentLevelResource(pub usize);
 
 #[derive(Resource)]
-pub struct CurrentDirection(pub Direction);
+pub struct ScoreStorageResource(pub Box<dyn ScoreStorage + Send + Sync>);
+
+impl Default for ScoreStorageResource {
+    #[cfg(not(any(test, target_arch = "wasm32")))]
+    fn default() -> Self {
+        Self(Box::new(snake::scores::FileStorage::new("scores")))
+    }
+
+    // No file system in the browser and no files left around by tests
+    #[cfg(any(test, target_arch = "wasm32"))]
+    fn default() -> Self {
+        Self(Box::<snake::scores::MemoryStorage>::default())
+    }
+}
+
+impl ScoreStorageResource {
+    /// Save the score of the game in the table of the level.
+    /// Returns the position in the table: `Some(0)` is a new record.
+    /// Hex boards have their own tables.
+    pub fn submit(&mut self, level: &str, game: &AnyGame) -> Option<usize> {
+        let snapshot = game.last_snapshot();
+        let entry = ScoreEntry {
+            name: PLAYER_NAME.to_owned(),
+            score: snapshot.score,
+            length: snapshot.snake.len(),
+            date: now(),
+            replay: game.replay(),
+        };
+
+        let table = match game {
+            AnyGame::Square(_) => level.to_owned(),
+            AnyGame::Hex(_) => format!("{}-hex", level),
+        };
+        match self.0.submit(&table, entry) {
+            Ok(rank) => rank,
+            Err(e) => {
+                tracing::error!("Unable to save the score: {}", e);
+                None
+            }
+        }
+    }
+}
+
+const PLAYER_NAME: &str = "player";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+// `SystemTime::now` panics in the browser
+#[cfg(target_arch = "wasm32")]
+fn now() -> u64 {
+    0
+}
 
 #[derive(Clone, Copy)]
 pub enum BundleType {
@@ -46,6 +312,7 @@ pub struct Assets {
     pub normal_button_color: Color,
     pub hovered_button_color: Color,
     pub pressed_button_color: Color,
+    pub locked_button_color: Color,
     pub text_button_color: Color,
     pub text_color: Color,
     pub overlay_background_color: Color,
@@ -73,6 +340,7 @@ impl FromWorld for Assets {
             normal_button_color: Color::rgb(0.15, 0.15, 0.15),
             hovered_button_color: Color::rgb(0.25, 0.25, 0.25),
             pressed_button_color: Color::rgb(0.35, 0.75, 0.35),
+            locked_button_color: Color::rgb(0.45, 0.15, 0.15),
             text_button_color: Color::WHITE,
             text_color: Color::WHITE,
             overlay_background_color: Color::rgba(0.6, 0.6, 0.6, 0.6),
@@ -0,0 +1,234 @@
+//! Stream games over WebSocket. Native only.
+//!
+//! With `SNAKE_BROADCAST=127.0.0.1:7879` the games played are streamed,
+//! with `SNAKE_SPECTATE=ws://127.0.0.1:7879` a stream is watched without
+//! playing.
+
+use std::{
+    env,
+    sync::{
+        mpsc::{self, Receiver},
+        Mutex,
+    },
+    thread,
+};
+
+use bevy::{
+    prelude::{
+        Commands, Entity, Or, Plugin, Query, Res, ResMut, Resource, SystemSet, TextBundle, With,
+    },
+    text::TextStyle,
+    window::Windows,
+};
+use snake::stream::{Frame, View};
+use snake_server::spectate::Broadcaster;
+use tracing::{error, info, warn};
+use tungstenite::Message;
+
+use crate::{
+    draw_utils::{DrawConfigurationResource, Layout},
+    play_plugin::{camera::calculate_cell_size, components::*},
+    resources::{AnyGame, Assets, BundleType, GameResource, ViewportResource},
+    AppState,
+};
+
+pub const BROADCAST_ENV: &str = "SNAKE_BROADCAST";
+pub const SPECTATE_ENV: &str = "SNAKE_SPECTATE";
+
+pub struct SpectatePlugin;
+
+impl Plugin for SpectatePlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app
+            // Broadcast
+            .add_system_set(SystemSet::on_enter(AppState::Play).with_system(restart_broadcast))
+            .add_system_set(SystemSet::on_update(AppState::Play).with_system(broadcast))
+            // Spectate
+            .add_system_set(SystemSet::on_enter(AppState::Spectate).with_system(connect))
+            .add_system_set(SystemSet::on_update(AppState::Spectate).with_system(draw_frames));
+    }
+}
+
+#[derive(Resource)]
+struct BroadcasterResource {
+    broadcaster: Broadcaster,
+    /// Ticks of the game already streamed
+    ticks: usize,
+}
+
+/// Frames received by the WebSocket thread
+#[derive(Resource)]
+struct FramesResource(Mutex<Receiver<Frame>>);
+
+#[derive(Resource, Default)]
+struct ViewResource(View);
+
+/// Every level is a new stream: the spectators get its board
+fn restart_broadcast(
+    mut commands: Commands,
+    game: Res<GameResource>,
+    broadcaster: Option<ResMut<BroadcasterResource>>,
+) {
+    if let Some(mut broadcaster) = broadcaster {
+        match &game.0 {
+            AnyGame::Square(game) => broadcaster.broadcaster.restart(game),
+            AnyGame::Hex(game) => broadcaster.broadcaster.restart(game),
+        }
+        broadcaster.ticks = 0;
+        return;
+    }
+
+    let addr = match env::var(BROADCAST_ENV) {
+        Ok(addr) => addr,
+        Err(_) => return,
+    };
+    let broadcaster = match &game.0 {
+        AnyGame::Square(game) => Broadcaster::bind(&addr, game),
+        AnyGame::Hex(game) => Broadcaster::bind(&addr, game),
+    };
+    match broadcaster {
+        Ok(broadcaster) => {
+            info!("Broadcasting on {}", broadcaster.local_addr());
+            commands.insert_resource(BroadcasterResource {
+                broadcaster,
+                ticks: 0,
+            });
+        }
+        Err(e) => error!("Unable to broadcast on {}: {}", addr, e),
+    }
+}
+
+fn broadcast(game: Res<GameResource>, broadcaster: Option<ResMut<BroadcasterResource>>) {
+    let mut broadcaster = match broadcaster {
+        Some(broadcaster) => broadcaster,
+        None => return,
+    };
+
+    let ticks = game.0.ticks();
+    if ticks != broadcaster.ticks {
+        broadcaster.ticks = ticks;
+        broadcaster.broadcaster.push(game.0.last_snapshot());
+    }
+}
+
+fn connect(mut commands: Commands) {
+    let url = env::var(SPECTATE_ENV).unwrap_or_default();
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let (mut ws, _) = match tungstenite::connect(url.as_str()) {
+            Ok(ws) => ws,
+            Err(e) => {
+                error!("Unable to spectate {}: {}", url, e);
+                return;
+            }
+        };
+
+        while let Ok(message) = ws.read() {
+            let text = match message {
+                Message::Text(text) => text,
+                _ => continue,
+            };
+            match text.parse() {
+                Ok(frame) => {
+                    if sender.send(frame).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => warn!("{}", e),
+            }
+        }
+        info!("Stream ended");
+    });
+
+    commands.insert_resource(FramesResource(Mutex::new(receiver)));
+    commands.insert_resource(ViewResource::default());
+}
+
+/// Apply the frames received and draw the board again
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn draw_frames(
+    mut commands: Commands,
+    frames: Res<FramesResource>,
+    mut view: ResMut<ViewResource>,
+    windows: Res<Windows>,
+    viewport: Res<ViewportResource>,
+    assets: Res<Assets>,
+    board_query: Query<
+        Entity,
+        Or<(
+            With<BackgroundComponent>,
+            With<WallComponent>,
+            With<SnakeHeadComponent>,
+            With<SnakeBodyComponent>,
+            With<FoodComponent>,
+            With<ScoreComponent>,
+        )>,
+    >,
+) {
+    let frames: Vec<Frame> = frames.0.lock().unwrap().try_iter().collect();
+    if frames.is_empty() {
+        return;
+    }
+    for frame in frames {
+        if let Err(e) = view.0.apply(frame) {
+            warn!("{}", e);
+        }
+    }
+
+    let view = &view.0;
+    let snapshot = match view.snapshot() {
+        Some(snapshot) => snapshot,
+        None => return,
+    };
+
+    for entity in board_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let dim = view.dim();
+    // The stream doesn't say how the cells are connected: they are drawn
+    // on a square grid
+    let layout = Layout::Square;
+    let drawing_configuration = DrawConfigurationResource {
+        cell_size: calculate_cell_size(layout.size_in_cells(dim), windows.primary(), &viewport),
+        dim,
+        layout,
+    };
+
+    drawing_configuration.spawn_background(&mut commands, &assets, view.voids());
+    for wall in view.walls() {
+        drawing_configuration.spawn(&mut commands, &assets, BundleType::Wall, wall);
+    }
+
+    let mut snake_iter = snapshot.snake.iter();
+    if let Some(head) = snake_iter.next() {
+        drawing_configuration.spawn(&mut commands, &assets, BundleType::SnakeHead, head);
+    }
+    for body in snake_iter {
+        drawing_configuration.spawn(&mut commands, &assets, BundleType::SnakeBody, body);
+    }
+    drawing_configuration.spawn(
+        &mut commands,
+        &assets,
+        BundleType::Food,
+        &snapshot.food_position,
+    );
+
+    let mut score = format!("score: {}", snapshot.score);
+    if let Some(reason) = snapshot.get_game_over_reason() {
+        score = format!("{} - {}", score, reason);
+    }
+    commands
+        .spawn(TextBundle::from_section(
+            score,
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: 30.0,
+                color: assets.text_button_color,
+            },
+        ))
+        .insert(ScoreComponent);
+
+    commands.insert_resource(drawing_configuration);
+}
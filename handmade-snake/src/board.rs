@@ -0,0 +1,149 @@
+use snake::{Point, Snapshot};
+use wasm_bindgen::prelude::*;
+
+/// What a cell of the board shows: the byte stored in the board buffer
+#[wasm_bindgen]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cell {
+    Empty = 0,
+    Wall = 1,
+    Void = 2,
+    Food = 3,
+    SnakeBody = 4,
+    SnakeHead = 5,
+}
+
+impl Cell {
+    pub fn from_byte(byte: u8) -> Cell {
+        match byte {
+            1 => Cell::Wall,
+            2 => Cell::Void,
+            3 => Cell::Food,
+            4 => Cell::SnakeBody,
+            5 => Cell::SnakeHead,
+            _ => Cell::Empty,
+        }
+    }
+}
+
+/// One byte per cell, row by row from the top. The buffer is allocated once
+/// and updated in place on every tick, so JS can keep a view on it.
+#[derive(Debug, Default)]
+pub struct Board {
+    width: usize,
+    height: usize,
+    /// Walls and voids: what every update starts from
+    terrain: Vec<u8>,
+    cells: Vec<u8>,
+}
+
+impl Board {
+    pub fn new((width, height): (usize, usize), walls: &[Point], voids: &[Point]) -> Self {
+        let mut board = Self {
+            width,
+            height,
+            terrain: vec![Cell::Empty as u8; width * height],
+            cells: vec![Cell::Empty as u8; width * height],
+        };
+        for p in voids {
+            board.set_terrain(p, Cell::Void);
+        }
+        for p in walls {
+            board.set_terrain(p, Cell::Wall);
+        }
+        board.cells.copy_from_slice(&board.terrain);
+
+        board
+    }
+
+    /// Cells painted by hand with `set_cell`, as the level editor does
+    pub fn blank(dim: (usize, usize)) -> Self {
+        Self::new(dim, &[], &[])
+    }
+
+    /// The cell at `column`, `row`: rows count from the top
+    pub fn cell(&self, column: usize, row: usize) -> Option<Cell> {
+        if column < self.width && row < self.height {
+            Some(Cell::from_byte(self.cells[row * self.width + column]))
+        } else {
+            None
+        }
+    }
+
+    /// Paint the cell at `column`, `row`, ignored outside of the board.
+    /// It stays till the next `update`.
+    pub fn set_cell(&mut self, column: usize, row: usize, cell: Cell) {
+        if column < self.width && row < self.height {
+            self.cells[row * self.width + column] = cell as u8;
+        }
+    }
+
+    pub fn dim(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn cells(&self) -> &[u8] {
+        &self.cells
+    }
+
+    /// Show `snapshot` on the terrain
+    pub fn update(&mut self, snapshot: &Snapshot) {
+        self.cells.copy_from_slice(&self.terrain);
+        self.set(&snapshot.food_position, Cell::Food);
+        for p in &snapshot.snake[1..] {
+            self.set(p, Cell::SnakeBody);
+        }
+        self.set(&snapshot.snake[0], Cell::SnakeHead);
+    }
+
+    fn index(&self, p: &Point) -> Option<usize> {
+        if p.x < self.width && p.y < self.height {
+            // The y of the game grows upwards
+            Some((self.height - 1 - p.y) * self.width + p.x)
+        } else {
+            None
+        }
+    }
+
+    fn set_terrain(&mut self, p: &Point, cell: Cell) {
+        if let Some(i) = self.index(p) {
+            self.terrain[i] = cell as u8;
+        }
+    }
+
+    fn set(&mut self, p: &Point, cell: Cell) {
+        if let Some(i) = self.index(p) {
+            self.cells[i] = cell as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use snake::{Direction, Game};
+
+    use super::{Board, Cell};
+
+    #[test]
+    fn test_board() {
+        let mut game: Game = "#####\n#   #\n#hbf#\n#####".parse().unwrap();
+        let mut board = Board::new(game.dim(), &game.walls(), &game.voids());
+        board.update(&game.last_snapshot());
+
+        let cells: Vec<_> = board.cells().iter().map(|c| Cell::from_byte(*c)).collect();
+        assert_eq!(cells[0], Cell::Wall);
+        assert_eq!(cells[6..9], [Cell::Empty, Cell::Empty, Cell::Empty]);
+        assert_eq!(
+            cells[11..14],
+            [Cell::SnakeHead, Cell::SnakeBody, Cell::Food]
+        );
+
+        let ptr = board.cells().as_ptr();
+        game.tick(Direction::Up);
+        board.update(&game.last_snapshot());
+        assert_eq!(board.cells().as_ptr(), ptr);
+        assert_eq!(Cell::from_byte(board.cells()[6]), Cell::SnakeHead);
+        assert_eq!(Cell::from_byte(board.cells()[11]), Cell::SnakeBody);
+    }
+}
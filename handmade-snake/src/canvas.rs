@@ -0,0 +1,200 @@
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement};
+
+use crate::{
+    board::{Board, Cell},
+    GameWrapper, LevelEditor, SpectatorWrapper, WorkerGameProxy,
+};
+
+/// The sprites of `bevy-snake/assets`
+struct Sprites {
+    background: HtmlImageElement,
+    wall: HtmlImageElement,
+    food: HtmlImageElement,
+    snake_body: HtmlImageElement,
+    snake_head: HtmlImageElement,
+}
+
+impl Sprites {
+    fn load(assets_url: &str) -> Result<Self, JsValue> {
+        let load = |name: &str| -> Result<HtmlImageElement, JsValue> {
+            let image = HtmlImageElement::new()?;
+            image.set_src(&format!("{}/{}", assets_url, name));
+            Ok(image)
+        };
+
+        Ok(Self {
+            background: load("background.png")?,
+            wall: load("wall.png")?,
+            food: load("food.png")?,
+            snake_body: load("snake_body.png")?,
+            snake_head: load("snake_head.png")?,
+        })
+    }
+
+    fn all(&self) -> [&HtmlImageElement; 5] {
+        [
+            &self.background,
+            &self.wall,
+            &self.food,
+            &self.snake_body,
+            &self.snake_head,
+        ]
+    }
+
+    fn is_loaded(&self) -> bool {
+        self.all()
+            .iter()
+            .all(|image| image.complete() && image.natural_width() > 0)
+    }
+
+    fn get(&self, cell: Cell) -> Option<&HtmlImageElement> {
+        match cell {
+            Cell::Empty | Cell::Void => None,
+            Cell::Wall => Some(&self.wall),
+            Cell::Food => Some(&self.food),
+            Cell::SnakeBody => Some(&self.snake_body),
+            Cell::SnakeHead => Some(&self.snake_head),
+        }
+    }
+}
+
+/// Colors used till the sprites are loaded
+fn fallback_color(cell: Cell) -> &'static str {
+    match cell {
+        Cell::Empty | Cell::Void => "#ffffff",
+        Cell::Wall => "#333333",
+        Cell::Food => "#d62828",
+        Cell::SnakeBody => "#52b788",
+        Cell::SnakeHead => "#2d6a4f",
+    }
+}
+
+/// Draws a game on a canvas. Only the cells changed since the previous
+/// draw are painted again.
+#[wasm_bindgen]
+pub struct CanvasRenderer {
+    canvas: HtmlCanvasElement,
+    context: CanvasRenderingContext2d,
+    sprites: Sprites,
+    /// Side of a cell in pixels
+    cell_size: u32,
+    dim: (usize, usize),
+    /// The cells on the canvas: empty when everything has to be drawn
+    drawn: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl CanvasRenderer {
+    /// Draw on `canvas` loading the sprites from `assets_url`
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        canvas: HtmlCanvasElement,
+        cell_size: u32,
+        assets_url: &str,
+    ) -> Result<CanvasRenderer, JsValue> {
+        let context = canvas
+            .get_context("2d")?
+            .ok_or("No 2d context")?
+            .dyn_into::<CanvasRenderingContext2d>()?;
+
+        Ok(Self {
+            canvas,
+            context,
+            sprites: Sprites::load(assets_url)?,
+            cell_size,
+            dim: (0, 0),
+            drawn: vec![],
+        })
+    }
+
+    /// Whether the sprites are loaded: till then cells are plain colors
+    pub fn is_loaded(&self) -> bool {
+        self.sprites.is_loaded()
+    }
+
+    pub fn cell_size(&self) -> u32 {
+        self.cell_size
+    }
+
+    /// Scale the board: everything is drawn again
+    pub fn set_cell_size(&mut self, cell_size: u32) {
+        self.cell_size = cell_size;
+        self.resize(self.dim);
+    }
+
+    pub fn draw(&mut self, game: &GameWrapper) {
+        self.draw_board(&game.board);
+    }
+
+    pub fn draw_spectator(&mut self, spectator: &SpectatorWrapper) {
+        if spectator.view.snapshot().is_some() {
+            self.draw_board(&spectator.board);
+        }
+    }
+
+    pub fn draw_editor(&mut self, editor: &LevelEditor) {
+        self.draw_board(&editor.board);
+    }
+
+    pub fn draw_worker_game(&mut self, game: &WorkerGameProxy) {
+        let mirror = game.mirror.borrow();
+        if mirror.view.snapshot().is_some() {
+            self.draw_board(&mirror.board);
+        }
+    }
+}
+
+impl CanvasRenderer {
+    fn resize(&mut self, (width, height): (usize, usize)) {
+        self.dim = (width, height);
+        self.canvas.set_width(width as u32 * self.cell_size);
+        self.canvas.set_height(height as u32 * self.cell_size);
+        // Resizing resets the context: keep the pixel art sharp when scaled
+        self.context.set_image_smoothing_enabled(false);
+        self.drawn.clear();
+    }
+
+    fn draw_board(&mut self, board: &Board) {
+        let dim = board.dim();
+        if dim != self.dim {
+            self.resize(dim);
+        }
+
+        let loaded = self.sprites.is_loaded();
+        let size = self.cell_size as f64;
+        for (i, byte) in board.cells().iter().enumerate() {
+            if self.drawn.get(i) == Some(byte) {
+                continue;
+            }
+
+            let x = (i % dim.0) as f64 * size;
+            let y = (i / dim.0) as f64 * size;
+            self.context.clear_rect(x, y, size, size);
+            let cell = Cell::from_byte(*byte);
+            if cell == Cell::Void {
+                continue;
+            }
+
+            if !loaded {
+                self.context.set_fill_style_str(fallback_color(cell));
+                self.context.fill_rect(x, y, size, size);
+                continue;
+            }
+
+            let sprites = [Some(&self.sprites.background), self.sprites.get(cell)];
+            for sprite in sprites.iter().flatten() {
+                // Nothing to do if it fails: the cell stays empty
+                let _ = self
+                    .context
+                    .draw_image_with_html_image_element_and_dw_and_dh(sprite, x, y, size, size);
+            }
+        }
+
+        // Till the sprites arrive every draw paints every cell
+        self.drawn.clear();
+        if loaded {
+            self.drawn.extend_from_slice(board.cells());
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use snake::Game;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    board::{Board, Cell},
+    board_view, to_js_error,
+};
+
+/// Longest side of a level, in cells
+const MAX_SIDE: usize = 100;
+
+fn cell_to_char(cell: Cell) -> char {
+    match cell {
+        Cell::Empty => ' ',
+        Cell::Wall => '#',
+        Cell::Void => '.',
+        Cell::Food => 'f',
+        Cell::SnakeBody => 'b',
+        Cell::SnakeHead => 'h',
+    }
+}
+
+fn char_to_cell(c: char) -> Option<Cell> {
+    match c {
+        ' ' => Some(Cell::Empty),
+        '#' => Some(Cell::Wall),
+        '.' => Some(Cell::Void),
+        'f' => Some(Cell::Food),
+        'b' => Some(Cell::SnakeBody),
+        'h' => Some(Cell::SnakeHead),
+        _ => None,
+    }
+}
+
+fn clamp_dim(width: usize, height: usize) -> (usize, usize) {
+    (width.clamp(1, MAX_SIDE), height.clamp(1, MAX_SIDE))
+}
+
+/// A level painted cell by cell. Cells are addressed by `column` and `row`,
+/// with the rows counted from the top as on the screen.
+#[wasm_bindgen(js_name = LevelEditor)]
+pub struct LevelEditor {
+    pub(crate) board: Board,
+}
+
+#[wasm_bindgen(js_class = LevelEditor)]
+impl LevelEditor {
+    /// Longest side of a level, in cells
+    pub fn max_side() -> usize {
+        MAX_SIDE
+    }
+
+    /// An empty level. Sides are kept between 1 and `max_side()` cells.
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: usize, height: usize) -> LevelEditor {
+        Self {
+            board: Board::blank(clamp_dim(width, height)),
+        }
+    }
+
+    /// Edit a level written like `SNAKE_1`: it may be invalid, but only with
+    /// the chars of a level and no side longer than `max_side()`.
+    /// The missing cells at the end of the rows are void.
+    pub fn from_level(level: &str) -> Result<LevelEditor, JsValue> {
+        let height = level.lines().count();
+        let width = level.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+        if width > MAX_SIDE || height > MAX_SIDE {
+            return Err(to_js_error(format!(
+                "Level of {}x{} cells: sides are at most {} cells",
+                width, height, MAX_SIDE
+            )));
+        }
+
+        let mut editor = Self::new(width, height);
+        for (row, line) in level.lines().enumerate() {
+            let mut chars = line.chars();
+            for column in 0..width {
+                let cell = match chars.next() {
+                    Some(c) => char_to_cell(c).ok_or_else(|| {
+                        to_js_error(format!("Invalid char {} at row {}", c, row + 1))
+                    })?,
+                    None => Cell::Void,
+                };
+                editor.board.set_cell(column, row, cell);
+            }
+        }
+
+        Ok(editor)
+    }
+
+    pub fn width(&self) -> usize {
+        self.board.dim().0
+    }
+
+    pub fn height(&self) -> usize {
+        self.board.dim().1
+    }
+
+    /// `undefined` outside of the level
+    pub fn cell(&self, column: usize, row: usize) -> Option<Cell> {
+        self.board.cell(column, row)
+    }
+
+    /// Paint a cell. There is only one head and one food: painting them
+    /// moves them.
+    pub fn paint(&mut self, column: usize, row: usize, cell: Cell) {
+        if self.cell(column, row).is_none() {
+            return;
+        }
+
+        if cell == Cell::SnakeHead || cell == Cell::Food {
+            let (width, height) = self.board.dim();
+            for r in 0..height {
+                for c in 0..width {
+                    if self.board.cell(c, r) == Some(cell) {
+                        self.board.set_cell(c, r, Cell::Empty);
+                    }
+                }
+            }
+        }
+        self.board.set_cell(column, row, cell);
+    }
+
+    /// Change the size keeping the cells at the top left. The new cells are
+    /// empty. Sides are kept between 1 and `max_side()` cells.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let (width, height) = clamp_dim(width, height);
+        let mut board = Board::blank((width, height));
+        for row in 0..height {
+            for column in 0..width {
+                if let Some(cell) = self.board.cell(column, row) {
+                    board.set_cell(column, row, cell);
+                }
+            }
+        }
+        self.board = board;
+    }
+
+    /// The level, written like `SNAKE_1`. The voids at the end of the rows
+    /// are left out, as the parser reads them.
+    pub fn level(&self) -> String {
+        let (width, height) = self.board.dim();
+        let rows: Vec<String> = (0..height)
+            .map(|row| {
+                let line: String = (0..width)
+                    .filter_map(|column| self.board.cell(column, row))
+                    .map(cell_to_char)
+                    .collect();
+                // An empty last row would be lost
+                let trimmed = line.trim_end_matches('.');
+                if trimmed.is_empty() && !line.is_empty() {
+                    ".".to_owned()
+                } else {
+                    trimmed.to_owned()
+                }
+            })
+            .collect();
+        rows.join("\n")
+    }
+
+    /// Why the level cannot be played, as the parser says, or `undefined`
+    pub fn error(&self) -> Option<String> {
+        self.level().parse::<Game>().err()
+    }
+
+    /// Like `Game.board`. Take the view again after `resize`.
+    pub fn board(&self) -> js_sys::Uint8Array {
+        board_view(&self.board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use snake::{SNAKE_1, SNAKE_2, SNAKE_4};
+
+    use super::{Cell, LevelEditor};
+
+    #[test]
+    fn test_paint() {
+        let mut editor = LevelEditor::new(3, 4);
+        assert_eq!(editor.error().unwrap(), "Missing snake head 'h'");
+
+        editor.paint(1, 0, Cell::SnakeHead);
+        editor.paint(1, 1, Cell::SnakeBody);
+        editor.paint(1, 2, Cell::Food);
+        editor.paint(2, 2, Cell::Food);
+        editor.paint(0, 3, Cell::Wall);
+        editor.paint(5, 3, Cell::Wall);
+        assert_eq!(editor.cell(1, 2), Some(Cell::Empty));
+        assert_eq!(editor.cell(5, 3), None);
+        assert_eq!(editor.level(), " h \n b \n  f\n#  ");
+        assert_eq!(editor.error(), None);
+
+        editor.resize(2, 2);
+        assert_eq!(editor.level(), " h\n b");
+        assert_eq!(editor.error().unwrap(), "Missing food 'f'");
+
+        editor.resize(0, 1000);
+        assert_eq!(
+            (editor.width(), editor.height()),
+            (1, LevelEditor::max_side())
+        );
+
+        // The snake needs a body to move
+        let editor = LevelEditor::from_level("#####\n# hf#\n#####").unwrap();
+        assert_eq!(editor.error().unwrap(), "Missing snake body 'b'");
+    }
+
+    #[test]
+    fn test_level() {
+        for level in [SNAKE_1, SNAKE_2, SNAKE_4].iter() {
+            assert_eq!(LevelEditor::from_level(level).unwrap().level(), *level);
+        }
+
+        let mut editor = LevelEditor::from_level("#h#\n#b#\n#f#").unwrap();
+        editor.paint(0, 2, Cell::Void);
+        editor.paint(1, 2, Cell::Void);
+        editor.paint(2, 2, Cell::Void);
+        assert_eq!(editor.level(), "#h#\n#b#\n.");
+    }
+}
@@ -0,0 +1,351 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    rc::Rc,
+};
+
+use snake::Direction;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{EventTarget, Gamepad, GamepadButton, KeyboardEvent, TouchEvent};
+
+use crate::DirectionWrapper;
+
+/// Directions pressed faster than the ticks wait here
+const QUEUE_LEN: usize = 3;
+/// Shortest swipe, in CSS pixels
+const SWIPE_THRESHOLD: f64 = 30.;
+/// How far a gamepad stick must be pushed
+const STICK_THRESHOLD: f64 = 0.5;
+
+/// The directions pressed and not used by a tick yet, oldest first
+#[derive(Debug, Default)]
+pub struct DirectionQueue {
+    directions: VecDeque<Direction>,
+    /// Where the snake goes, when known: the last direction taken
+    heading: Option<Direction>,
+}
+
+impl DirectionQueue {
+    /// Add `direction` unless it changes nothing after the last one, or
+    /// after the heading when the queue is empty: the same direction or
+    /// the opposite one, which the game ignores
+    pub fn push(&mut self, direction: Direction) {
+        if let Some(last) = self.directions.back().or(self.heading.as_ref()) {
+            if *last == direction || opposite(*last) == direction {
+                return;
+            }
+        }
+        if self.directions.len() < QUEUE_LEN {
+            self.directions.push_back(direction);
+        }
+    }
+
+    /// The popped direction becomes the heading
+    pub fn pop(&mut self) -> Option<Direction> {
+        let direction = self.directions.pop_front();
+        if direction.is_some() {
+            self.heading = direction;
+        }
+        direction
+    }
+
+    pub fn set_heading(&mut self, direction: Direction) {
+        self.heading = Some(direction);
+    }
+
+    pub fn clear(&mut self) {
+        self.directions.clear();
+        self.heading = None;
+    }
+}
+
+fn opposite(direction: Direction) -> Direction {
+    match direction {
+        Direction::Up => Direction::Down,
+        Direction::Down => Direction::Up,
+        Direction::Left => Direction::Right,
+        Direction::Right => Direction::Left,
+    }
+}
+
+/// Arrows and WASD, by `KeyboardEvent.code` so WASD stays in place on
+/// every keyboard layout
+fn default_bindings() -> HashMap<String, Direction> {
+    [
+        ("ArrowUp", Direction::Up),
+        ("ArrowDown", Direction::Down),
+        ("ArrowLeft", Direction::Left),
+        ("ArrowRight", Direction::Right),
+        ("KeyW", Direction::Up),
+        ("KeyS", Direction::Down),
+        ("KeyA", Direction::Left),
+        ("KeyD", Direction::Right),
+    ]
+    .iter()
+    .map(|(code, direction)| (code.to_string(), *direction))
+    .collect()
+}
+
+/// The direction of a swipe of `dx`, `dy` pixels. The y of the screen grows
+/// downwards.
+pub fn swipe_direction(dx: f64, dy: f64, threshold: f64) -> Option<Direction> {
+    if dx.abs().max(dy.abs()) < threshold {
+        return None;
+    }
+
+    Some(if dx.abs() > dy.abs() {
+        if dx > 0. {
+            Direction::Right
+        } else {
+            Direction::Left
+        }
+    } else if dy > 0. {
+        Direction::Down
+    } else {
+        Direction::Up
+    })
+}
+
+/// The direction of a gamepad with the standard mapping: d-pad first,
+/// then the left stick
+fn gamepad_direction(gamepad: &Gamepad) -> Option<Direction> {
+    let buttons = gamepad.buttons();
+    let pressed = |index: u32| {
+        buttons
+            .get(index)
+            .dyn_into::<GamepadButton>()
+            .is_ok_and(|button| button.pressed())
+    };
+    let dpad = [
+        (12, Direction::Up),
+        (13, Direction::Down),
+        (14, Direction::Left),
+        (15, Direction::Right),
+    ];
+    if let Some((_, direction)) = dpad.iter().find(|(index, _)| pressed(*index)) {
+        return Some(*direction);
+    }
+
+    let axes = gamepad.axes();
+    let x = axes.get(0).as_f64().unwrap_or_default();
+    let y = axes.get(1).as_f64().unwrap_or_default();
+    swipe_direction(x, y, STICK_THRESHOLD)
+}
+
+#[derive(Default)]
+struct State {
+    queue: DirectionQueue,
+    /// `KeyboardEvent.code` to direction
+    bindings: HashMap<String, Direction>,
+    swipe_threshold: f64,
+    /// Where the current touch started
+    touch_start: Option<(f64, f64)>,
+    /// Direction of the gamepads at the previous poll: a direction is
+    /// queued only when it is pressed
+    gamepad: Option<Direction>,
+}
+
+type Listener = Closure<dyn FnMut(web_sys::Event)>;
+
+/// Listens to the keyboard, to swipes and to gamepads and queues the
+/// directions. The listeners are removed when it is dropped.
+#[wasm_bindgen]
+pub struct Controls {
+    state: Rc<RefCell<State>>,
+    /// The target of every listener with its event name
+    listeners: Vec<(EventTarget, &'static str, Listener)>,
+}
+
+#[wasm_bindgen]
+impl Controls {
+    /// Listen to the keyboard on the whole page and to swipes on
+    /// `touch_area`
+    #[wasm_bindgen(constructor)]
+    pub fn new(touch_area: EventTarget) -> Result<Controls, JsValue> {
+        let document: EventTarget = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or("No document")?
+            .into();
+
+        let state = Rc::new(RefCell::new(State {
+            bindings: default_bindings(),
+            swipe_threshold: SWIPE_THRESHOLD,
+            ..State::default()
+        }));
+        let mut controls = Self {
+            state,
+            listeners: vec![],
+        };
+
+        controls.listen(&document, "keydown", |state, event| {
+            let event = match event.dyn_ref::<KeyboardEvent>() {
+                Some(event) => event,
+                None => return,
+            };
+            if let Some(direction) = state.bindings.get(&event.code()).copied() {
+                // Arrows would scroll the page
+                event.prevent_default();
+                state.queue.push(direction);
+            }
+        })?;
+        controls.listen(&touch_area, "touchstart", |state, event| {
+            let touch = event
+                .dyn_ref::<TouchEvent>()
+                .and_then(|event| event.changed_touches().get(0));
+            state.touch_start =
+                touch.map(|touch| (touch.client_x() as f64, touch.client_y() as f64));
+        })?;
+        controls.listen(&touch_area, "touchend", |state, event| {
+            let touch = event
+                .dyn_ref::<TouchEvent>()
+                .and_then(|event| event.changed_touches().get(0));
+            if let (Some((x, y)), Some(touch)) = (state.touch_start.take(), touch) {
+                let dx = touch.client_x() as f64 - x;
+                let dy = touch.client_y() as f64 - y;
+                if let Some(direction) = swipe_direction(dx, dy, state.swipe_threshold) {
+                    state.queue.push(direction);
+                }
+            }
+        })?;
+
+        Ok(controls)
+    }
+
+    /// Move towards `direction` when the key with `KeyboardEvent.code`
+    /// `code` is pressed
+    pub fn bind_key(&mut self, code: String, direction: DirectionWrapper) {
+        self.state
+            .borrow_mut()
+            .bindings
+            .insert(code, direction.into());
+    }
+
+    pub fn unbind_key(&mut self, code: &str) {
+        self.state.borrow_mut().bindings.remove(code);
+    }
+
+    /// Remove every key binding, the default ones included
+    pub fn clear_bindings(&mut self) {
+        self.state.borrow_mut().bindings.clear();
+    }
+
+    /// Shortest swipe, in CSS pixels
+    pub fn set_swipe_threshold(&mut self, threshold: f64) {
+        self.state.borrow_mut().swipe_threshold = threshold;
+    }
+
+    /// The oldest direction not taken yet. The gamepads are read first: they
+    /// have no events.
+    pub fn next_direction(&mut self) -> Option<DirectionWrapper> {
+        self.poll_gamepads();
+        self.pop().map(Into::into)
+    }
+
+    /// The direction the snake goes: while nothing is queued, a key for it
+    /// or for the opposite one changes nothing and is not queued.
+    /// `GameRunner` sets it on every frame.
+    pub fn set_heading(&mut self, direction: DirectionWrapper) {
+        self.state.borrow_mut().queue.set_heading(direction.into());
+    }
+
+    /// Forget the directions not taken yet and the heading
+    pub fn clear(&mut self) {
+        self.state.borrow_mut().queue.clear();
+    }
+}
+
+impl Controls {
+    fn listen(
+        &mut self,
+        target: &EventTarget,
+        name: &'static str,
+        mut handler: impl FnMut(&mut State, &web_sys::Event) + 'static,
+    ) -> Result<(), JsValue> {
+        let state = self.state.clone();
+        let listener = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            handler(&mut state.borrow_mut(), &event);
+        }) as Box<dyn FnMut(web_sys::Event)>);
+
+        target.add_event_listener_with_callback(name, listener.as_ref().unchecked_ref())?;
+        self.listeners.push((target.clone(), name, listener));
+        Ok(())
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<Direction> {
+        self.state.borrow_mut().queue.pop()
+    }
+
+    pub(crate) fn poll_gamepads(&mut self) {
+        let gamepads = match web_sys::window().map(|window| window.navigator().get_gamepads()) {
+            Some(Ok(gamepads)) => gamepads,
+            _ => return,
+        };
+        let direction = gamepads
+            .iter()
+            .filter_map(|gamepad| gamepad.dyn_into::<Gamepad>().ok())
+            .find_map(|gamepad| gamepad_direction(&gamepad));
+
+        let mut state = self.state.borrow_mut();
+        if direction != state.gamepad {
+            state.gamepad = direction;
+            if let Some(direction) = direction {
+                state.queue.push(direction);
+            }
+        }
+    }
+}
+
+impl Drop for Controls {
+    fn drop(&mut self) {
+        for (target, name, listener) in &self.listeners {
+            let _ =
+                target.remove_event_listener_with_callback(name, listener.as_ref().unchecked_ref());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use snake::Direction;
+
+    use super::{swipe_direction, DirectionQueue};
+
+    #[test]
+    fn test_direction_queue() {
+        let mut queue = DirectionQueue::default();
+        queue.push(Direction::Up);
+        queue.push(Direction::Up);
+        queue.push(Direction::Down);
+        queue.push(Direction::Left);
+        queue.push(Direction::Down);
+        queue.push(Direction::Right);
+
+        assert_eq!(queue.pop(), Some(Direction::Up));
+        assert_eq!(queue.pop(), Some(Direction::Left));
+        assert_eq!(queue.pop(), Some(Direction::Down));
+        assert_eq!(queue.pop(), None);
+
+        // Empty, the queue checks the heading: the last direction popped
+        queue.push(Direction::Up);
+        queue.push(Direction::Down);
+        queue.set_heading(Direction::Right);
+        queue.push(Direction::Left);
+        queue.push(Direction::Right);
+        queue.push(Direction::Up);
+        assert_eq!(queue.pop(), Some(Direction::Up));
+        assert_eq!(queue.pop(), None);
+
+        queue.clear();
+        queue.push(Direction::Left);
+        assert_eq!(queue.pop(), Some(Direction::Left));
+    }
+
+    #[test]
+    fn test_swipe_direction() {
+        assert_eq!(swipe_direction(10., -5., 30.), None);
+        assert_eq!(swipe_direction(40., -5., 30.), Some(Direction::Right));
+        assert_eq!(swipe_direction(-40., 35., 30.), Some(Direction::Left));
+        assert_eq!(swipe_direction(5., 40., 30.), Some(Direction::Down));
+        assert_eq!(swipe_direction(5., -40., 30.), Some(Direction::Up));
+    }
+}
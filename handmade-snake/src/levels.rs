@@ -0,0 +1,40 @@
+use std::cell::RefCell;
+
+use snake::{Game, SNAKE_1, SNAKE_2};
+
+thread_local! {
+    /// The levels offered to the player, in order
+    static LEVELS: RefCell<Vec<(String, String)>> = RefCell::new(vec![
+        ("snake1".to_owned(), SNAKE_1.to_owned()),
+        ("snake2".to_owned(), SNAKE_2.to_owned()),
+    ]);
+}
+
+pub fn get(name: &str) -> Option<String> {
+    LEVELS.with(|levels| {
+        levels
+            .borrow()
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, level)| level.clone())
+    })
+}
+
+/// Add a level, or replace the one with the same name.
+/// Fails if the level cannot be parsed.
+pub fn register(name: String, level: String) -> Result<(), String> {
+    level.parse::<Game>()?;
+
+    LEVELS.with(|levels| {
+        let mut levels = levels.borrow_mut();
+        match levels.iter_mut().find(|(n, _)| *n == name) {
+            Some(existing) => existing.1 = level,
+            None => levels.push((name, level)),
+        }
+    });
+    Ok(())
+}
+
+pub fn all() -> Vec<(String, String)> {
+    LEVELS.with(|levels| levels.borrow().clone())
+}
@@ -1,7 +1,36 @@
+mod board;
+mod canvas;
+mod editor;
+mod input;
+mod levels;
+mod runner;
+mod scores;
+mod share;
+mod types;
 mod utils;
+mod worker;
 
-use js_sys::{Int32Array, Object};
-use snake::{Direction, Game, Snapshot, SNAKE_1, SNAKE_2};
+pub use board::Cell;
+pub use canvas::CanvasRenderer;
+pub use editor::LevelEditor;
+pub use input::Controls;
+pub use runner::GameRunner;
+pub use share::ShareWrapper;
+pub use worker::{start_worker, WorkerGameProxy};
+
+use std::time::Duration;
+
+use board::Board;
+use js_sys::Uint8Array;
+use scores::LocalStorage;
+use share::Playback;
+use snake::{
+    replay::Replay,
+    scores::{ScoreEntry, ScoreStorage},
+    share::Share,
+    stream::{Frame, View},
+    Direction, Game, Snapshot, TickOutcome,
+};
 use tracing::info;
 use tracing_subscriber::{
     fmt::{
@@ -11,6 +40,7 @@ use tracing_subscriber::{
     prelude::__tracing_subscriber_SubscriberExt,
     util::SubscriberInitExt,
 };
+use types::{JsDim, JsGameOverReason, JsLevels, JsPoint, JsPoints};
 use wasm_bindgen::prelude::*;
 
 extern crate web_sys;
@@ -28,17 +58,7 @@ macro_rules! _log {
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-#[wasm_bindgen]
-extern "C" {
-    fn alert(s: &str);
-}
-
-#[wasm_bindgen]
-pub fn greet() {
-    alert("Hello, handly-made!");
-}
-
-#[wasm_bindgen]
+#[wasm_bindgen(js_name = Direction)]
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DirectionWrapper {
@@ -48,88 +68,288 @@ pub enum DirectionWrapper {
     Right = 3,
 }
 
-#[wasm_bindgen]
-pub struct PointWrapper(usize, usize);
+impl From<DirectionWrapper> for Direction {
+    fn from(direction: DirectionWrapper) -> Self {
+        match direction {
+            DirectionWrapper::Up => Direction::Up,
+            DirectionWrapper::Down => Direction::Down,
+            DirectionWrapper::Left => Direction::Left,
+            DirectionWrapper::Right => Direction::Right,
+        }
+    }
+}
 
-#[wasm_bindgen]
-pub struct GameWrapper(Game);
+impl From<Direction> for DirectionWrapper {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Up => DirectionWrapper::Up,
+            Direction::Down => DirectionWrapper::Down,
+            Direction::Left => DirectionWrapper::Left,
+            Direction::Right => DirectionWrapper::Right,
+        }
+    }
+}
+
+/// A game: create it with `create_game`
+#[wasm_bindgen(js_name = Game)]
+pub struct GameWrapper {
+    /// Written like `SNAKE_1`, for `share_code`
+    level: String,
+    game: Game,
+    /// Updated after every tick
+    board: Board,
+    /// Set on the games that play a shared replay back
+    playback: Option<Playback>,
+}
 
-#[wasm_bindgen]
 impl GameWrapper {
-    pub fn dim(&self) -> Int32Array {
-        let dim = self.0.dim();
+    /// A game on `level`, playing `playback` back from its start if any
+    fn start(level: String, playback: Option<&Playback>) -> Result<Self, String> {
+        let (game, playback) = match playback {
+            Some(playback) => {
+                let (game, playback) = playback.start(&level)?;
+                (game, Some(playback))
+            }
+            None => (level.parse()?, None),
+        };
+
+        let mut board = Board::new(game.dim(), &game.walls(), &game.voids());
+        board.update(&game.last_snapshot());
+        Ok(Self {
+            level,
+            game,
+            board,
+            playback,
+        })
+    }
+
+    /// The same level from the start, as a replay again for a replay
+    fn restarted(&self) -> Self {
+        // The level was parsed when the game was created
+        Self::start(self.level.clone(), self.playback.as_ref()).unwrap()
+    }
 
-        let dim = vec![dim.0 as i32, dim.1 as i32];
+    /// Like `advance`, returning the outcome of every tick
+    fn advance_by(&mut self, elapsed: Duration) -> Vec<TickOutcome> {
+        let outcomes = match &mut self.playback {
+            Some(playback) => playback.advance(&mut self.game, elapsed),
+            None => self.game.advance(elapsed),
+        };
+        if !outcomes.is_empty() {
+            self.board.update(&self.game.last_snapshot());
+        }
+        outcomes
+    }
+}
+
+#[wasm_bindgen(js_class = Game)]
+impl GameWrapper {
+    pub fn dim(&self) -> JsDim {
+        types::dim(self.game.dim())
+    }
 
-        Int32Array::from(&dim[..])
+    pub fn walls(&self) -> JsPoints {
+        types::points(&self.game.walls())
     }
 
-    pub fn walls(&self) -> Int32Array {
-        let walls: Vec<i32> = self
-            .0
-            .walls()
-            .into_iter()
-            .flat_map(|p| vec![p.x as i32, p.y as i32].into_iter())
-            .collect();
+    pub fn voids(&self) -> JsPoints {
+        types::points(&self.game.voids())
+    }
 
-        Int32Array::from(&walls[..])
+    /// The board, one `Cell` per byte, row by row from the top.
+    /// The view is on the wasm memory and follows the game without copies:
+    /// take it again if it gets empty, which happens when the memory grows.
+    /// It is read-only and only valid while this board lives: once the
+    /// game is freed, or its board replaced (by `GameRunner.restart`, a new
+    /// board frame in `Spectator.apply`, `LevelEditor.resize`), it shows
+    /// freed memory that gets reused. Nothing detaches it then.
+    pub fn board(&self) -> Uint8Array {
+        board_view(&self.board)
     }
 
+    /// Ignored by the games that play a replay back
     pub fn tick(&mut self, direction: DirectionWrapper) {
         info!("direction = {:?}", direction);
+        if self.playback.is_some() {
+            return;
+        }
 
-        let direction = match direction {
-            DirectionWrapper::Up => Direction::Up,
-            DirectionWrapper::Down => Direction::Down,
-            DirectionWrapper::Left => Direction::Left,
-            DirectionWrapper::Right => Direction::Right,
-        };
+        self.game.tick(direction.into());
+        self.board.update(&self.game.last_snapshot());
+    }
 
-        self.0.tick(direction);
+    pub fn set_direction(&mut self, direction: DirectionWrapper) {
+        self.game.set_direction(direction.into());
+    }
+
+    /// Let `elapsed_ms` milliseconds pass. Returns the number of ticks run.
+    pub fn advance(&mut self, elapsed_ms: f64) -> usize {
+        let elapsed = Duration::from_secs_f64(elapsed_ms.max(0.) / 1000.);
+        self.advance_by(elapsed).len()
     }
 
     pub fn last_snapshot(&self) -> SnapshotWrapper {
-        SnapshotWrapper(self.0.last_snapshot())
+        SnapshotWrapper(self.game.last_snapshot())
+    }
+
+    /// Created by `Share.create_game` to play a replay back
+    pub fn is_playback(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    /// The level and the moves so far, to open with `Share.decode`
+    pub fn share_code(&self) -> String {
+        // The level was parsed when the game was created
+        Share::new(self.level.clone(), Some(self.game.replay()))
+            .unwrap()
+            .to_string()
+    }
+
+    /// Save the score in the high score table of the level.
+    /// Returns the position in the table (`0` is a new record) or `null`.
+    pub fn save_score(&self, level_name: String, player_name: String) -> Result<JsValue, JsValue> {
+        save_score(
+            &self.game.last_snapshot(),
+            self.game.replay(),
+            level_name,
+            player_name,
+        )
     }
 }
 
-#[wasm_bindgen]
+fn save_score(
+    snapshot: &Snapshot,
+    replay: Replay,
+    level_name: String,
+    player_name: String,
+) -> Result<JsValue, JsValue> {
+    let entry = ScoreEntry {
+        name: player_name,
+        score: snapshot.score,
+        length: snapshot.snake.len(),
+        date: (js_sys::Date::now() / 1000.) as u64,
+        replay: Some(replay),
+    };
+
+    let mut storage = LocalStorage::new().map_err(|e| JsValue::from_str(&e))?;
+    let rank = storage
+        .submit(&level_name, entry)
+        .map_err(|e| JsValue::from_str(&e))?;
+
+    Ok(match rank {
+        Some(rank) => JsValue::from(rank as u32),
+        None => JsValue::NULL,
+    })
+}
+
+/// The state after a tick
+#[wasm_bindgen(js_name = Snapshot)]
 pub struct SnapshotWrapper(Snapshot);
 
-#[wasm_bindgen]
+#[wasm_bindgen(js_class = Snapshot)]
 impl SnapshotWrapper {
-    pub fn snake(&self) -> Int32Array {
-        let s: Vec<_> = self
-            .0
-            .snake
-            .iter()
-            .cloned()
-            .flat_map(|p| vec![p.x as i32, p.y as i32].into_iter())
-            .collect();
-        js_sys::Int32Array::from(&s[..])
+    /// Head first
+    #[wasm_bindgen(getter)]
+    pub fn snake(&self) -> JsPoints {
+        types::points(&self.0.snake)
     }
 
-    pub fn food(&self) -> Int32Array {
-        let dim = vec![self.0.food_position.x as i32, self.0.food_position.y as i32];
-        Int32Array::from(&dim[..])
+    #[wasm_bindgen(getter)]
+    pub fn head(&self) -> JsPoint {
+        types::point(&self.0.snake[0])
     }
 
-    pub fn get_game_over_reason(&self) -> JsValue {
-        if let Some(reason) = self.0.get_game_over_reason() {
-            return JsValue::from_str(reason);
-        }
+    #[wasm_bindgen(getter)]
+    pub fn food(&self) -> JsPoint {
+        types::point(&self.0.food_position)
+    }
 
-        JsValue::NULL
+    /// `undefined` while the snake is alive
+    #[wasm_bindgen(getter)]
+    pub fn game_over_reason(&self) -> Option<JsGameOverReason> {
+        self.0.get_game_over_reason().map(types::game_over_reason)
     }
 
+    #[wasm_bindgen(getter)]
     pub fn score(&self) -> usize {
         self.0.score
     }
 
+    #[wasm_bindgen(getter)]
     pub fn period_duration_ms(&self) -> usize {
         self.0.period_duration.as_millis() as usize
     }
 }
+
+/// A view on the cells, valid till the board is dropped or replaced
+fn board_view(board: &Board) -> Uint8Array {
+    // No Rust code runs between the creation of the view and its return to
+    // JS, so the memory cannot grow under it. Then the cells are updated
+    // in place, never reallocated: the view follows them. Nothing stops JS
+    // from keeping the view after the board is dropped or replaced: it then
+    // reads reused memory, which gives wrong cells but cannot break Rust.
+    // Only writes through the view could, which is why the docs make it
+    // read-only.
+    unsafe { Uint8Array::view(board.cells()) }
+}
+
+/// Follows a game streamed by `snake-server`: every WebSocket message is
+/// passed to `apply`
+#[wasm_bindgen(js_name = Spectator)]
+pub struct SpectatorWrapper {
+    view: View,
+    /// Updated by every frame
+    board: Board,
+}
+
+#[wasm_bindgen(js_class = Spectator)]
+impl SpectatorWrapper {
+    pub fn apply(&mut self, frame: String) -> Result<(), JsValue> {
+        let frame: Frame = frame.parse().map_err(|e: String| JsValue::from_str(&e))?;
+        let new_board = matches!(frame, Frame::Board { .. });
+        self.view.apply(frame).map_err(|e| JsValue::from_str(&e))?;
+
+        if new_board {
+            self.board = Board::new(self.view.dim(), self.view.walls(), self.view.voids());
+        }
+        if let Some(snapshot) = self.view.snapshot() {
+            self.board.update(snapshot);
+        }
+        Ok(())
+    }
+
+    pub fn dim(&self) -> JsDim {
+        types::dim(self.view.dim())
+    }
+
+    pub fn walls(&self) -> JsPoints {
+        types::points(self.view.walls())
+    }
+
+    pub fn voids(&self) -> JsPoints {
+        types::points(self.view.voids())
+    }
+
+    /// Like `Game.board`. A new board frame replaces the board: take the
+    /// view again after it.
+    pub fn board(&self) -> Uint8Array {
+        board_view(&self.board)
+    }
+
+    /// `undefined` till the first snapshot arrives
+    pub fn last_snapshot(&self) -> Option<SnapshotWrapper> {
+        self.view.snapshot().cloned().map(SnapshotWrapper)
+    }
+}
+
+#[wasm_bindgen]
+pub fn create_spectator() -> SpectatorWrapper {
+    SpectatorWrapper {
+        view: View::default(),
+        board: Board::default(),
+    }
+}
+
 #[wasm_bindgen]
 pub fn set_panic_hook() {
     utils::set_panic_hook();
@@ -147,24 +367,33 @@ pub fn set_panic_hook() {
         .init();
 }
 
-#[wasm_bindgen]
-pub fn create_game(level_name: JsValue) -> GameWrapper {
-    let level_name = level_name.as_string().unwrap();
-    let game: Game = match level_name.as_str() {
-        "snake1" => SNAKE_1.parse().unwrap(),
-        "snake2" => SNAKE_2.parse().unwrap(),
-        _ => panic!("Unknown level_name"),
-    };
+fn to_js_error(e: String) -> JsValue {
+    js_sys::Error::new(&e).into()
+}
 
-    GameWrapper(game)
+/// Start a game on a level returned by `levels()`
+#[wasm_bindgen]
+pub fn create_game(level_name: &str) -> Result<GameWrapper, JsValue> {
+    let level = levels::get(level_name)
+        .ok_or_else(|| to_js_error(format!("Unknown level {}", level_name)))?;
+    create_game_from_level(&level)
 }
 
+/// Start a game on a level written with `#`, `h`, `b`, `f`, ` ` and `.`.
+/// Throws an `Error` if the level is invalid.
 #[wasm_bindgen]
-pub fn levels() -> Object {
-    let levels = Object::new();
+pub fn create_game_from_level(level: &str) -> Result<GameWrapper, JsValue> {
+    GameWrapper::start(level.to_owned(), None).map_err(to_js_error)
+}
 
-    js_sys::Reflect::set(&levels, &"snake1".into(), &SNAKE_1.into()).unwrap();
-    js_sys::Reflect::set(&levels, &"snake2".into(), &SNAKE_2.into()).unwrap();
+/// Add a level to the ones returned by `levels()`, replacing the one with
+/// the same name. Throws an `Error` if the level is invalid.
+#[wasm_bindgen]
+pub fn register_level(name: String, level: String) -> Result<(), JsValue> {
+    levels::register(name, level).map_err(to_js_error)
+}
 
-    levels
+#[wasm_bindgen]
+pub fn levels() -> JsLevels {
+    types::levels(levels::all())
 }
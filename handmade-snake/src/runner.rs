@@ -0,0 +1,339 @@
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+    time::Duration,
+};
+
+use js_sys::{Function, Uint8Array};
+use snake::{Snapshot, TickOutcome};
+use wasm_bindgen::{prelude::*, JsCast};
+
+use crate::{
+    board_view,
+    canvas::CanvasRenderer,
+    create_game,
+    input::Controls,
+    types::{self, OnGameOver, OnRedraw, OnScore},
+    DirectionWrapper, GameWrapper, SnapshotWrapper,
+};
+
+/// Longest time a frame can advance the game: no frame runs while the tab
+/// is hidden and the snake should not jump ahead once it is back
+const MAX_FRAME_MS: f64 = 250.;
+
+/// What happened in a frame, told to the JS callbacks
+enum Event {
+    Redraw(Snapshot),
+    Score(usize),
+    GameOver(&'static str),
+}
+
+#[derive(Clone, Default)]
+struct Callbacks {
+    on_redraw: Option<Function>,
+    on_score: Option<Function>,
+    on_game_over: Option<Function>,
+}
+
+impl Callbacks {
+    fn emit(&self, event: Event) {
+        let (callback, arg) = match event {
+            Event::Redraw(snapshot) => (&self.on_redraw, SnapshotWrapper(snapshot).into()),
+            Event::Score(score) => (&self.on_score, JsValue::from(score as u32)),
+            Event::GameOver(reason) => (&self.on_game_over, types::game_over_reason(reason).into()),
+        };
+
+        if let Some(callback) = callback {
+            if let Err(e) = callback.call1(&JsValue::NULL, &arg) {
+                web_sys::console::error_2(&"GameRunner callback failed".into(), &e);
+            }
+        }
+    }
+}
+
+struct State {
+    game: GameWrapper,
+    renderer: Option<CanvasRenderer>,
+    controls: Option<Controls>,
+    /// A direction of `controls` is waiting for the next tick
+    direction_set: bool,
+    callbacks: Callbacks,
+    /// Timestamp of the previous frame: `None` on the first one
+    last_timestamp: Option<f64>,
+    /// Id of the requested animation frame while running
+    frame_id: Option<i32>,
+    /// Stopped by `pause`
+    paused: bool,
+    score: usize,
+}
+
+impl State {
+    /// Advance the game by the time passed since the previous frame
+    fn frame(&mut self, timestamp: f64) -> Vec<Event> {
+        let first = self.last_timestamp.is_none();
+        let elapsed = self
+            .last_timestamp
+            .map_or(0., |last| (timestamp - last).clamp(0., MAX_FRAME_MS));
+        self.last_timestamp = Some(timestamp);
+
+        // One queued direction for every tick
+        if let Some(controls) = &mut self.controls {
+            controls.poll_gamepads();
+            if !self.direction_set {
+                if let Some(direction) = controls.pop() {
+                    self.game.game.set_direction(direction);
+                    self.direction_set = true;
+                }
+            }
+        }
+
+        let outcomes = self
+            .game
+            .advance_by(Duration::from_secs_f64(elapsed / 1000.));
+        let snapshot = self.game.game.last_snapshot();
+        if !outcomes.is_empty() {
+            self.direction_set = false;
+        }
+        // A direction waiting for the tick is already the heading
+        if !self.direction_set {
+            if let Some(controls) = &mut self.controls {
+                controls.set_heading(self.game.game.direction().into());
+            }
+        }
+
+        let mut events = vec![];
+        if first || !outcomes.is_empty() {
+            if let Some(renderer) = &mut self.renderer {
+                renderer.draw(&self.game);
+            }
+            events.push(Event::Redraw(snapshot.clone()));
+        }
+        if first || snapshot.score != self.score {
+            self.score = snapshot.score;
+            events.push(Event::Score(snapshot.score));
+        }
+        if let Some(TickOutcome::GameOver(snapshot)) = outcomes.last() {
+            let reason = snapshot.get_game_over_reason().unwrap_or_default();
+            events.push(Event::GameOver(reason));
+        }
+
+        events
+    }
+}
+
+type FrameClosure = Closure<dyn FnMut(f64)>;
+
+/// Runs a game with `requestAnimationFrame`: every frame the game advances
+/// by the time passed and the callbacks are told what changed.
+#[wasm_bindgen]
+pub struct GameRunner {
+    state: Rc<RefCell<State>>,
+    /// The callback given to `requestAnimationFrame`
+    frame: Rc<RefCell<Option<FrameClosure>>>,
+}
+
+#[wasm_bindgen]
+impl GameRunner {
+    /// Take the game: it is only reachable through the runner afterwards
+    #[wasm_bindgen(constructor)]
+    pub fn new(game: GameWrapper) -> GameRunner {
+        let state = Rc::new(RefCell::new(State {
+            game,
+            renderer: None,
+            controls: None,
+            direction_set: false,
+            callbacks: Callbacks::default(),
+            last_timestamp: None,
+            frame_id: None,
+            paused: false,
+            score: 0,
+        }));
+        let frame = Rc::new(RefCell::new(None));
+
+        let closure = {
+            let state = state.clone();
+            let frame = Rc::downgrade(&frame);
+            Closure::wrap(Box::new(move |timestamp: f64| {
+                on_animation_frame(&state, &frame, timestamp);
+            }) as Box<dyn FnMut(f64)>)
+        };
+        *frame.borrow_mut() = Some(closure);
+
+        Self { state, frame }
+    }
+
+    /// Draw the board on every tick
+    pub fn set_renderer(&mut self, renderer: CanvasRenderer) {
+        self.state.borrow_mut().renderer = Some(renderer);
+    }
+
+    /// Take the directions from `controls`, one for every tick
+    pub fn set_controls(&mut self, controls: Controls) {
+        self.state.borrow_mut().controls = Some(controls);
+    }
+
+    /// `callback(snapshot)` is called on start and after every tick
+    pub fn on_redraw(&mut self, callback: OnRedraw) {
+        self.state.borrow_mut().callbacks.on_redraw = Some(callback.unchecked_into());
+    }
+
+    /// `callback(score)` is called on start and when the score changes
+    pub fn on_score(&mut self, callback: OnScore) {
+        self.state.borrow_mut().callbacks.on_score = Some(callback.unchecked_into());
+    }
+
+    /// `callback(reason)` is called when the snake dies. The runner stops.
+    pub fn on_game_over(&mut self, callback: OnGameOver) {
+        self.state.borrow_mut().callbacks.on_game_over = Some(callback.unchecked_into());
+    }
+
+    pub fn set_direction(&mut self, direction: DirectionWrapper) {
+        self.state
+            .borrow_mut()
+            .game
+            .game
+            .set_direction(direction.into());
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state.borrow().frame_id.is_some()
+    }
+
+    pub fn start(&mut self) -> Result<(), JsValue> {
+        if self.is_running() {
+            return Ok(());
+        }
+        let frame = self.frame.borrow();
+        let closure = frame.as_ref().ok_or("GameRunner is dropped")?;
+        let frame_id = request_animation_frame(closure)?;
+        self.state.borrow_mut().frame_id = Some(frame_id);
+        Ok(())
+    }
+
+    /// Stop ticking. The time till the next `start` does not count.
+    pub fn stop(&mut self) {
+        let mut state = self.state.borrow_mut();
+        if let Some(frame_id) = state.frame_id.take() {
+            if let Some(window) = web_sys::window() {
+                let _ = window.cancel_animation_frame(frame_id);
+            }
+        }
+        state.last_timestamp = None;
+    }
+
+    /// Stop till `resume`. Does nothing if the runner is not running.
+    pub fn pause(&mut self) {
+        if self.is_running() {
+            self.stop();
+            self.state.borrow_mut().paused = true;
+        }
+    }
+
+    pub fn resume(&mut self) -> Result<(), JsValue> {
+        if !self.is_paused() {
+            return Ok(());
+        }
+        self.state.borrow_mut().paused = false;
+        self.start()
+    }
+
+    pub fn toggle_pause(&mut self) -> Result<(), JsValue> {
+        if self.is_paused() {
+            self.resume()
+        } else {
+            self.pause();
+            Ok(())
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.borrow().paused
+    }
+
+    /// Play again from the start of a level returned by `levels()`, as
+    /// `create_game` does, keeping the renderer, the controls and the
+    /// callbacks. Without a name, the level of the game starts again, and
+    /// a shared replay plays back again. Take `board()` again: the game
+    /// has a new one.
+    pub fn restart(&mut self, level_name: Option<String>) -> Result<(), JsValue> {
+        let game = match level_name {
+            Some(level_name) => create_game(&level_name)?,
+            None => self.state.borrow().game.restarted(),
+        };
+        self.stop();
+        {
+            let mut state = self.state.borrow_mut();
+            state.game = game;
+            state.paused = false;
+            state.direction_set = false;
+            state.score = 0;
+            if let Some(controls) = &mut state.controls {
+                controls.clear();
+            }
+        }
+        self.start()
+    }
+
+    /// See `Game.board`. Take the view again after `restart`.
+    pub fn board(&self) -> Uint8Array {
+        board_view(&self.state.borrow().game.board)
+    }
+
+    pub fn last_snapshot(&self) -> SnapshotWrapper {
+        self.state.borrow().game.last_snapshot()
+    }
+
+    /// See `Game.is_playback`
+    pub fn is_playback(&self) -> bool {
+        self.state.borrow().game.is_playback()
+    }
+
+    /// See `Game.share_code`
+    pub fn share_code(&self) -> String {
+        self.state.borrow().game.share_code()
+    }
+
+    /// See `Game.save_score`
+    pub fn save_score(&self, level_name: String, player_name: String) -> Result<JsValue, JsValue> {
+        self.state.borrow().game.save_score(level_name, player_name)
+    }
+}
+
+impl Drop for GameRunner {
+    fn drop(&mut self) {
+        self.stop();
+        // The closure keeps the state alive
+        self.frame.borrow_mut().take();
+    }
+}
+
+fn request_animation_frame(closure: &FrameClosure) -> Result<i32, JsValue> {
+    web_sys::window()
+        .ok_or("No window")?
+        .request_animation_frame(closure.as_ref().unchecked_ref())
+}
+
+fn on_animation_frame(
+    state: &Rc<RefCell<State>>,
+    frame: &Weak<RefCell<Option<FrameClosure>>>,
+    timestamp: f64,
+) {
+    let events = state.borrow_mut().frame(timestamp);
+    let over = state.borrow().game.game.is_over();
+
+    // Ask for the next frame before the callbacks, which may stop the runner
+    let next = match frame.upgrade() {
+        Some(frame) if !over => frame
+            .borrow()
+            .as_ref()
+            .and_then(|closure| request_animation_frame(closure).ok()),
+        _ => None,
+    };
+    state.borrow_mut().frame_id = next;
+
+    // The state is not borrowed: the callbacks can use the runner
+    let callbacks = state.borrow().callbacks.clone();
+    for event in events {
+        callbacks.emit(event);
+    }
+}
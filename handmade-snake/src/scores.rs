@@ -0,0 +1,37 @@
+use snake::scores::{HighScoreTable, ScoreStorage};
+use web_sys::Storage;
+
+/// Keep the high score tables in the `localStorage` of the browser
+pub struct LocalStorage(Storage);
+
+impl LocalStorage {
+    pub fn new() -> Result<Self, String> {
+        let storage = web_sys::window()
+            .ok_or("No window")?
+            .local_storage()
+            .map_err(|e| format!("{:?}", e))?
+            .ok_or("No localStorage")?;
+
+        Ok(Self(storage))
+    }
+
+    fn key(level: &str) -> String {
+        format!("snake-scores-{}", level)
+    }
+}
+
+impl ScoreStorage for LocalStorage {
+    fn load(&self, level: &str) -> Result<HighScoreTable, String> {
+        match self.0.get_item(&Self::key(level)) {
+            Ok(Some(content)) => content.parse(),
+            Ok(None) => Ok(HighScoreTable::default()),
+            Err(e) => Err(format!("{:?}", e)),
+        }
+    }
+
+    fn save(&mut self, level: &str, table: &HighScoreTable) -> Result<(), String> {
+        self.0
+            .set_item(&Self::key(level), &table.to_string())
+            .map_err(|e| format!("{:?}", e))
+    }
+}
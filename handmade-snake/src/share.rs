@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use snake::{replay::Replay, share::Share, Game, TickOutcome};
+use wasm_bindgen::prelude::*;
+
+use crate::{to_js_error, GameWrapper};
+
+/// The directions of a replay, taken one per tick in place of the player's
+pub(crate) struct Playback {
+    replay: Replay,
+    /// The index of the next direction to take
+    next: usize,
+    accumulator: Duration,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next: 0,
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    /// A game on `level` to play the replay back on, from the start
+    pub fn start(&self, level: &str) -> Result<(Game, Playback), String> {
+        let mut game: Game = level.parse()?;
+        game.set_seed(self.replay.seed);
+        Ok((game, Playback::new(self.replay.clone())))
+    }
+
+    /// Like `Game::advance`. The game stops when the directions run out.
+    pub fn advance(&mut self, game: &mut Game, elapsed: Duration) -> Vec<TickOutcome> {
+        let mut outcomes = vec![];
+        if game.is_over() || self.next == self.replay.directions.len() {
+            return outcomes;
+        }
+
+        self.accumulator += elapsed;
+        while self.accumulator >= game.last_snapshot().period_duration {
+            let direction = match self.replay.directions.get(self.next) {
+                Some(direction) => *direction,
+                None => break,
+            };
+            self.next += 1;
+            self.accumulator -= game.last_snapshot().period_duration;
+            game.tick(direction);
+
+            let snapshot = game.last_snapshot();
+            if game.is_over() {
+                outcomes.push(TickOutcome::GameOver(snapshot));
+                break;
+            }
+            outcomes.push(TickOutcome::Moved(snapshot));
+        }
+
+        outcomes
+    }
+}
+
+/// A level, with a game to watch on it, written as a short code that fits
+/// in the fragment of a link. `bevy-snake` reads the same codes.
+#[wasm_bindgen(js_name = Share)]
+pub struct ShareWrapper(Share);
+
+#[wasm_bindgen(js_class = Share)]
+impl ShareWrapper {
+    /// Throws an `Error` if the code or its level is invalid
+    pub fn decode(code: &str) -> Result<ShareWrapper, JsValue> {
+        code.parse().map(ShareWrapper).map_err(to_js_error)
+    }
+
+    /// Share only a level. Throws an `Error` if it is invalid.
+    pub fn from_level(level: String) -> Result<ShareWrapper, JsValue> {
+        Share::new(level, None)
+            .map(ShareWrapper)
+            .map_err(to_js_error)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn level(&self) -> String {
+        self.0.level().to_owned()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn has_replay(&self) -> bool {
+        self.0.replay().is_some()
+    }
+
+    pub fn code(&self) -> String {
+        self.0.to_string()
+    }
+
+    /// A game on the level. With a replay, it plays the replay back and
+    /// ignores the directions it is given.
+    pub fn create_game(&self) -> GameWrapper {
+        let playback = self.0.replay().cloned().map(Playback::new);
+        // The level was checked by `Share`
+        GameWrapper::start(self.level(), playback.as_ref()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use snake::{Direction, Game, TickOutcome, SNAKE_2};
+
+    use super::Playback;
+
+    #[test]
+    fn test_playback() {
+        let mut game: Game = SNAKE_2.parse().unwrap();
+        game.set_seed(3);
+        for direction in [Direction::Up, Direction::Right, Direction::Right] {
+            game.tick(direction);
+        }
+        let replay = game.replay();
+
+        let (mut replayed, mut playback) = Playback::new(replay).start(SNAKE_2).unwrap();
+        let period = replayed.last_snapshot().period_duration;
+
+        assert!(playback.advance(&mut replayed, period / 2).is_empty());
+        let outcomes = playback.advance(&mut replayed, period * 10);
+        assert_eq!(outcomes.len(), 3);
+        assert!(matches!(outcomes[2], TickOutcome::Moved(_)));
+        assert_eq!(replayed.last_snapshot().snake, game.last_snapshot().snake);
+
+        // The directions ran out
+        assert!(playback.advance(&mut replayed, period).is_empty());
+
+        // Started again, it plays the same game
+        let (mut again, mut playback) = playback.start(SNAKE_2).unwrap();
+        assert_eq!(playback.advance(&mut again, period * 10).len(), 3);
+        assert_eq!(again.last_snapshot().snake, game.last_snapshot().snake);
+    }
+}
@@ -0,0 +1,98 @@
+//! Plain JS values returned to JS, with their TypeScript typings
+
+use js_sys::{Array, Object, Reflect};
+use snake::Point;
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPES: &'static str = r#"
+/** A cell of the board: `y` grows upwards, `{x: 0, y: 0}` is bottom left */
+export interface Point {
+    x: number;
+    y: number;
+}
+
+export interface Dim {
+    width: number;
+    height: number;
+}
+
+/** Why the snake died */
+export type GameOverReason = "On Wall" | "Eat itself";
+
+/** Levels by name, written like `SNAKE_1` */
+export type Levels = Record<string, string>;
+
+export type OnRedraw = (snapshot: Snapshot) => void;
+export type OnScore = (score: number) => void;
+export type OnGameOver = (reason: GameOverReason) => void;
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(extends = Object, typescript_type = "Point")]
+    pub type JsPoint;
+
+    #[wasm_bindgen(extends = Array, typescript_type = "Point[]")]
+    pub type JsPoints;
+
+    #[wasm_bindgen(extends = Object, typescript_type = "Dim")]
+    pub type JsDim;
+
+    #[wasm_bindgen(typescript_type = "GameOverReason")]
+    pub type JsGameOverReason;
+
+    #[wasm_bindgen(extends = Object, typescript_type = "Levels")]
+    pub type JsLevels;
+
+    #[wasm_bindgen(typescript_type = "OnRedraw")]
+    pub type OnRedraw;
+
+    #[wasm_bindgen(typescript_type = "OnScore")]
+    pub type OnScore;
+
+    #[wasm_bindgen(typescript_type = "OnGameOver")]
+    pub type OnGameOver;
+}
+
+fn object<K: AsRef<str>>(fields: impl IntoIterator<Item = (K, JsValue)>) -> Object {
+    let object = Object::new();
+    for (name, value) in fields {
+        // Setting a field of a new object cannot fail
+        Reflect::set(&object, &name.as_ref().into(), &value).unwrap();
+    }
+    object
+}
+
+pub fn point(p: &Point) -> JsPoint {
+    object([("x", (p.x as u32).into()), ("y", (p.y as u32).into())]).unchecked_into()
+}
+
+pub fn points(points: &[Point]) -> JsPoints {
+    points
+        .iter()
+        .map(|p| JsValue::from(point(p)))
+        .collect::<Array>()
+        .unchecked_into()
+}
+
+pub fn dim((width, height): (usize, usize)) -> JsDim {
+    object([
+        ("width", (width as u32).into()),
+        ("height", (height as u32).into()),
+    ])
+    .unchecked_into()
+}
+
+pub fn game_over_reason(reason: &'static str) -> JsGameOverReason {
+    JsValue::from_str(reason).unchecked_into()
+}
+
+pub fn levels(levels: impl IntoIterator<Item = (String, String)>) -> JsLevels {
+    object(
+        levels
+            .into_iter()
+            .map(|(name, level)| (name, JsValue::from(level))),
+    )
+    .unchecked_into()
+}
@@ -0,0 +1,463 @@
+//! Run a game in a Web Worker. The worker calls `start_worker`, the page
+//! talks to it through a `WorkerGame`.
+//!
+//! Every message is one string, which can span several lines: the page
+//! sends commands and the worker answers with the frames of `snake::stream`,
+//! like `snake-server` does.
+
+use std::{cell::RefCell, fmt, rc::Rc, str::FromStr, time::Duration};
+
+use js_sys::Function;
+use snake::{
+    replay::Replay,
+    share::Share,
+    stream::{Encoder, Frame, View},
+    Direction, Game, Snapshot, TickOutcome,
+};
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{DedicatedWorkerGlobalScope, MessageEvent, Worker};
+
+use crate::{
+    board::Board,
+    board_view, save_score, to_js_error,
+    types::{self, JsDim, JsPoints, OnRedraw},
+    DirectionWrapper, SnapshotWrapper,
+};
+
+/// Sent by the page to the worker
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Start a game on the level, replacing the previous one.
+    /// The level goes on the lines after `NEW`.
+    New(String),
+    Tick(Direction),
+    SetDirection(Direction),
+    /// Milliseconds passed
+    Advance(f64),
+}
+
+/// Sent by the worker to the page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    Frame(Frame),
+    /// Sent when the game is over
+    Replay(Replay),
+    Error(String),
+}
+
+fn direction_to_char(direction: Direction) -> char {
+    match direction {
+        Direction::Up => 'U',
+        Direction::Down => 'D',
+        Direction::Left => 'L',
+        Direction::Right => 'R',
+    }
+}
+
+fn parse_direction(s: &str) -> Result<Direction, String> {
+    match s {
+        "U" => Ok(Direction::Up),
+        "D" => Ok(Direction::Down),
+        "L" => Ok(Direction::Left),
+        "R" => Ok(Direction::Right),
+        _ => Err(format!("Invalid direction {}", s)),
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::New(level) => write!(f, "NEW\n{}", level),
+            Command::Tick(direction) => write!(f, "TICK {}", direction_to_char(*direction)),
+            Command::SetDirection(direction) => {
+                write!(f, "DIRECTION {}", direction_to_char(*direction))
+            }
+            Command::Advance(elapsed_ms) => write!(f, "ADVANCE {}", elapsed_ms),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(level) = s.strip_prefix("NEW\n") {
+            return Ok(Command::New(level.to_owned()));
+        }
+
+        let (name, arg) = s.split_once(' ').unwrap_or((s, ""));
+        match name {
+            "TICK" => Ok(Command::Tick(parse_direction(arg)?)),
+            "DIRECTION" => Ok(Command::SetDirection(parse_direction(arg)?)),
+            "ADVANCE" => arg
+                .parse()
+                .map(Command::Advance)
+                .map_err(|_| format!("Invalid elapsed time {}", arg)),
+            _ => Err(format!("Invalid command {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reply::Frame(frame) => write!(f, "{}", frame),
+            Reply::Replay(replay) => write!(f, "REPLAY {}", replay),
+            Reply::Error(error) => write!(f, "ERROR {}", error),
+        }
+    }
+}
+
+impl FromStr for Reply {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(replay) = s.strip_prefix("REPLAY ") {
+            return replay.parse().map(Reply::Replay);
+        }
+        if let Some(error) = s.strip_prefix("ERROR ") {
+            return Ok(Reply::Error(error.to_owned()));
+        }
+        s.parse().map(Reply::Frame)
+    }
+}
+
+/// The game run by the worker
+#[derive(Default)]
+struct Host {
+    game: Option<(Game, Encoder)>,
+}
+
+impl Host {
+    /// Run `command`, returning what the page has to know
+    fn run(&mut self, command: Command) -> Vec<Reply> {
+        match command {
+            Command::New(level) => match level.parse::<Game>() {
+                Ok(game) => {
+                    let encoder = Encoder::new(&game);
+                    let frames = encoder.catch_up();
+                    self.game = Some((game, encoder));
+                    frames.iter().cloned().map(Reply::Frame).collect()
+                }
+                Err(e) => vec![Reply::Error(e)],
+            },
+            Command::SetDirection(direction) => self.play(|game| {
+                game.set_direction(direction);
+                vec![]
+            }),
+            Command::Tick(direction) => self.play(|game| {
+                game.tick(direction);
+                vec![game.last_snapshot()]
+            }),
+            Command::Advance(elapsed_ms) => self.play(|game| {
+                let elapsed = Duration::from_secs_f64(elapsed_ms.max(0.) / 1000.);
+                game.advance(elapsed)
+                    .into_iter()
+                    .map(|outcome| match outcome {
+                        TickOutcome::Moved(snapshot) | TickOutcome::GameOver(snapshot) => snapshot,
+                    })
+                    .collect()
+            }),
+        }
+    }
+
+    /// Run `f` on the game and send a frame for every snapshot it returns
+    fn play(&mut self, f: impl FnOnce(&mut Game) -> Vec<Snapshot>) -> Vec<Reply> {
+        let (game, encoder) = match &mut self.game {
+            Some(game) => game,
+            None => return vec![Reply::Error("No game started".to_owned())],
+        };
+
+        let snapshots = f(game);
+        let mut replies = vec![];
+        // Before the frames: the page has it when it sees the game over
+        if !snapshots.is_empty() && game.is_over() {
+            replies.push(Reply::Replay(game.replay()));
+        }
+        for snapshot in snapshots {
+            replies.push(Reply::Frame(encoder.push(snapshot)));
+        }
+        replies
+    }
+}
+
+/// The entry point of the worker: run the commands sent by a `WorkerGame`
+#[wasm_bindgen]
+pub fn start_worker() -> Result<(), JsValue> {
+    let scope: DedicatedWorkerGlobalScope = js_sys::global().dyn_into()?;
+
+    let mut host = Host::default();
+    let post = scope.clone();
+    let on_message = Closure::wrap(Box::new(move |event: MessageEvent| {
+        let replies = match event.data().as_string().ok_or("Not a string") {
+            Ok(command) => match command.parse() {
+                Ok(command) => host.run(command),
+                Err(e) => vec![Reply::Error(e)],
+            },
+            Err(e) => vec![Reply::Error(e.to_owned())],
+        };
+        for reply in replies {
+            if let Err(e) = post.post_message(&reply.to_string().into()) {
+                web_sys::console::error_1(&e);
+            }
+        }
+    }) as Box<dyn FnMut(MessageEvent)>);
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    // The closure lives as long as the worker
+    on_message.forget();
+
+    Ok(())
+}
+
+/// What the page knows about the game in the worker
+#[derive(Default)]
+pub(crate) struct Mirror {
+    /// Sent with `NEW`, for `share_code`
+    level: String,
+    pub(crate) view: View,
+    pub(crate) board: Board,
+    replay: Option<Replay>,
+    on_change: Option<Function>,
+    on_error: Option<Function>,
+}
+
+impl Mirror {
+    /// Returns whether the snapshot changed
+    fn receive(&mut self, reply: Reply) -> Result<bool, String> {
+        match reply {
+            Reply::Frame(frame) => {
+                let new_board = matches!(frame, Frame::Board { .. });
+                self.view.apply(frame)?;
+                if new_board {
+                    self.board = Board::new(self.view.dim(), self.view.walls(), self.view.voids());
+                }
+                match self.view.snapshot() {
+                    Some(snapshot) => {
+                        self.board.update(snapshot);
+                        Ok(true)
+                    }
+                    None => Ok(false),
+                }
+            }
+            Reply::Replay(replay) => {
+                self.replay = Some(replay);
+                Ok(false)
+            }
+            Reply::Error(e) => Err(e),
+        }
+    }
+}
+
+/// A game run by a worker which called `start_worker`. It is not a drop-in
+/// `Game`: the commands run in the worker and the state changes when the
+/// worker answers, then `on_change` is called. So:
+/// - `advance` returns nothing, the ticks run are seen in `on_change`;
+/// - `dim`, `board` and `last_snapshot` are empty till the first answer;
+/// - `save_score` and `share_code` throw till the game is over;
+/// - there is no playback of a shared replay;
+/// - the errors of the worker go to `on_error`.
+#[wasm_bindgen(js_name = WorkerGame)]
+pub struct WorkerGameProxy {
+    worker: Worker,
+    pub(crate) mirror: Rc<RefCell<Mirror>>,
+    /// Kept to receive the answers of the worker
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+}
+
+#[wasm_bindgen(js_class = WorkerGame)]
+impl WorkerGameProxy {
+    /// Start a game on a level written like `SNAKE_1` in `worker`.
+    /// Throws an `Error` if the level is invalid.
+    #[wasm_bindgen(constructor)]
+    pub fn new(worker: Worker, level: String) -> Result<WorkerGameProxy, JsValue> {
+        // Check it here to throw like `create_game_from_level`
+        level.parse::<Game>().map_err(to_js_error)?;
+
+        let mirror = Rc::new(RefCell::new(Mirror {
+            level: level.clone(),
+            ..Mirror::default()
+        }));
+        let on_message = {
+            let mirror = mirror.clone();
+            Closure::wrap(Box::new(move |event: MessageEvent| {
+                let result = event
+                    .data()
+                    .as_string()
+                    .ok_or_else(|| "Not a string".to_owned())
+                    .and_then(|reply| reply.parse())
+                    .and_then(|reply| mirror.borrow_mut().receive(reply));
+
+                // Not borrowed: the callbacks can use the game
+                let (on_change, on_error, snapshot) = {
+                    let mirror = mirror.borrow();
+                    let snapshot = mirror.view.snapshot().cloned();
+                    (mirror.on_change.clone(), mirror.on_error.clone(), snapshot)
+                };
+                let (callback, arg) = match (result, snapshot) {
+                    (Err(e), _) => (on_error, to_js_error(e)),
+                    (Ok(true), Some(snapshot)) => (on_change, SnapshotWrapper(snapshot).into()),
+                    (Ok(_), _) => return,
+                };
+                if let Some(callback) = callback {
+                    let _ = callback.call1(&JsValue::NULL, &arg);
+                }
+            }) as Box<dyn FnMut(MessageEvent)>)
+        };
+        worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+        let proxy = Self {
+            worker,
+            mirror,
+            _on_message: on_message,
+        };
+        proxy.send(Command::New(level));
+        Ok(proxy)
+    }
+
+    /// `callback(snapshot)` is called when the worker sends a new state
+    pub fn on_change(&mut self, callback: OnRedraw) {
+        self.mirror.borrow_mut().on_change = Some(callback.unchecked_into());
+    }
+
+    /// `callback(error)` is called when the worker fails
+    pub fn on_error(&mut self, callback: Function) {
+        self.mirror.borrow_mut().on_error = Some(callback);
+    }
+
+    /// `{width: 0, height: 0}` till the worker answers
+    pub fn dim(&self) -> JsDim {
+        types::dim(self.mirror.borrow().view.dim())
+    }
+
+    pub fn walls(&self) -> JsPoints {
+        types::points(self.mirror.borrow().view.walls())
+    }
+
+    pub fn voids(&self) -> JsPoints {
+        types::points(self.mirror.borrow().view.voids())
+    }
+
+    /// Like `Game.board`. Take the view again after the first answer of the
+    /// worker, which sets the size of the board.
+    pub fn board(&self) -> js_sys::Uint8Array {
+        board_view(&self.mirror.borrow().board)
+    }
+
+    pub fn tick(&self, direction: DirectionWrapper) {
+        self.send(Command::Tick(direction.into()))
+    }
+
+    pub fn set_direction(&self, direction: DirectionWrapper) {
+        self.send(Command::SetDirection(direction.into()))
+    }
+
+    /// Let `elapsed_ms` milliseconds pass in the worker
+    pub fn advance(&self, elapsed_ms: f64) {
+        self.send(Command::Advance(elapsed_ms))
+    }
+
+    /// `undefined` till the worker answers
+    pub fn last_snapshot(&self) -> Option<SnapshotWrapper> {
+        self.mirror
+            .borrow()
+            .view
+            .snapshot()
+            .cloned()
+            .map(SnapshotWrapper)
+    }
+
+    /// Like `Game.share_code`, once the game is over
+    pub fn share_code(&self) -> Result<String, JsValue> {
+        let mirror = self.mirror.borrow();
+        let replay = mirror.replay.clone().ok_or_else(not_over)?;
+        Share::new(mirror.level.clone(), Some(replay))
+            .map(|share| share.to_string())
+            .map_err(to_js_error)
+    }
+
+    /// Like `Game.save_score`, once the game is over
+    pub fn save_score(&self, level_name: String, player_name: String) -> Result<JsValue, JsValue> {
+        let mirror = self.mirror.borrow();
+        match (mirror.view.snapshot(), &mirror.replay) {
+            (Some(snapshot), Some(replay)) => {
+                save_score(snapshot, replay.clone(), level_name, player_name)
+            }
+            _ => Err(not_over()),
+        }
+    }
+}
+
+fn not_over() -> JsValue {
+    to_js_error("The game is not over".to_owned())
+}
+
+impl WorkerGameProxy {
+    /// A failure goes to `on_error`, as the errors of the worker do
+    fn send(&self, command: Command) {
+        if let Err(e) = self.worker.post_message(&command.to_string().into()) {
+            let on_error = self.mirror.borrow().on_error.clone();
+            match on_error {
+                Some(callback) => {
+                    let _ = callback.call1(&JsValue::NULL, &e);
+                }
+                None => web_sys::console::error_1(&e),
+            }
+        }
+    }
+}
+
+impl Drop for WorkerGameProxy {
+    fn drop(&mut self) {
+        self.worker.set_onmessage(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use snake::{Direction, SNAKE_1};
+
+    use super::{Command, Host, Reply};
+
+    #[test]
+    fn test_messages() {
+        let commands = [
+            Command::New(SNAKE_1.to_owned()),
+            Command::Tick(Direction::Left),
+            Command::SetDirection(Direction::Down),
+            Command::Advance(16.5),
+        ];
+        for command in commands.iter() {
+            assert_eq!(command.to_string().parse::<Command>().unwrap(), *command);
+        }
+        assert_eq!(
+            Command::New("h\nb\nf".to_owned()).to_string(),
+            "NEW\nh\nb\nf"
+        );
+        assert!("JUMP".parse::<Command>().is_err());
+        assert!("TICK X".parse::<Command>().is_err());
+
+        let reply = Reply::Error("No game started".to_owned());
+        assert_eq!(reply.to_string().parse::<Reply>().unwrap(), reply);
+    }
+
+    #[test]
+    fn test_host() {
+        let mut host = Host::default();
+        let replies = host.run(Command::Tick(Direction::Up));
+        assert_eq!(replies, [Reply::Error("No game started".to_owned())]);
+
+        let replies = host.run(Command::New("#h#\n#b#\n# #\n#f#".to_owned()));
+        assert_eq!(replies.len(), 2);
+
+        assert!(host.run(Command::Advance(10.)).is_empty());
+        let replies = host.run(Command::Tick(Direction::Down));
+        assert_eq!(replies.len(), 1);
+
+        // Into the wall
+        let replies = host.run(Command::Tick(Direction::Right));
+        assert!(matches!(replies[..], [Reply::Replay(_), Reply::Frame(_)]));
+        for reply in replies {
+            assert_eq!(reply.to_string().parse::<Reply>().unwrap(), reply);
+        }
+    }
+}
@@ -11,3 +11,164 @@ wasm_bindgen_test_configure!(run_in_browser);
 fn pass() {
     assert_eq!(1 + 1, 2);
 }
+
+#[wasm_bindgen_test]
+fn create_game_from_level() {
+    use wasm_bindgen::JsCast;
+
+    assert!(handmade_snake::create_game_from_level("#h#\n#b#\n#f#").is_ok());
+
+    let error = handmade_snake::create_game_from_level("#h#\n#x#\n#f#")
+        .err()
+        .unwrap()
+        .dyn_into::<js_sys::Error>()
+        .unwrap();
+    assert_eq!(String::from(error.message()), "Invalid char x at 1,1");
+
+    let error = handmade_snake::create_game_from_level("#####\n# hf#\n#####")
+        .err()
+        .unwrap()
+        .dyn_into::<js_sys::Error>()
+        .unwrap();
+    assert_eq!(String::from(error.message()), "Missing snake body 'b'");
+}
+
+#[wasm_bindgen_test]
+fn register_level() {
+    assert!(handmade_snake::create_game("mine").is_err());
+    assert!(handmade_snake::register_level("mine".into(), "#x#".into()).is_err());
+
+    handmade_snake::register_level("mine".into(), "hf\nb ".into()).unwrap();
+    assert!(handmade_snake::create_game("mine").is_ok());
+
+    let levels = js_sys::Object::keys(&handmade_snake::levels());
+    assert_eq!(levels.length(), 3);
+    assert_eq!(levels.get(2), "mine");
+}
+
+#[wasm_bindgen_test]
+fn game_runner() {
+    let game = handmade_snake::create_game("snake1").unwrap();
+    let mut runner = handmade_snake::GameRunner::new(game);
+    assert!(!runner.is_running());
+
+    runner.start().unwrap();
+    assert!(runner.is_running());
+    runner.stop();
+    assert!(!runner.is_running());
+    assert_eq!(runner.last_snapshot().score(), 0);
+}
+
+#[wasm_bindgen_test]
+fn board() {
+    use handmade_snake::{Cell, DirectionWrapper};
+
+    let mut game = handmade_snake::create_game_from_level("###\n# #\n#h#\n#b#\n#f#\n###").unwrap();
+    let board = game.board();
+    assert_eq!(board.length(), 18);
+    assert_eq!(board.get_index(7), Cell::SnakeHead as u8);
+
+    // The view follows the game
+    game.tick(DirectionWrapper::Up);
+    assert_eq!(board.get_index(4), Cell::SnakeHead as u8);
+    assert_eq!(board.get_index(7), Cell::SnakeBody as u8);
+}
+
+#[wasm_bindgen_test]
+fn snapshot_getters() {
+    use js_sys::Reflect;
+
+    let game = handmade_snake::create_game_from_level("#h#\n#b#\n#f#").unwrap();
+    let snapshot = game.last_snapshot();
+
+    let head = snapshot.head();
+    assert_eq!(Reflect::get(&head, &"x".into()).unwrap().as_f64(), Some(1.));
+    assert_eq!(Reflect::get(&head, &"y".into()).unwrap().as_f64(), Some(2.));
+    assert_eq!(js_sys::Array::from(&snapshot.snake()).length(), 2);
+    assert!(snapshot.game_over_reason().is_none());
+
+    let dim = game.dim();
+    assert_eq!(
+        Reflect::get(&dim, &"height".into()).unwrap().as_f64(),
+        Some(3.)
+    );
+}
+
+#[wasm_bindgen_test]
+fn pause_and_restart() {
+    let game = handmade_snake::create_game("snake1").unwrap();
+    let mut runner = handmade_snake::GameRunner::new(game);
+
+    // Only a running game pauses
+    runner.pause();
+    assert!(!runner.is_paused());
+
+    runner.start().unwrap();
+    runner.pause();
+    assert!(runner.is_paused());
+    assert!(!runner.is_running());
+    runner.resume().unwrap();
+    assert!(!runner.is_paused());
+    assert!(runner.is_running());
+
+    runner.toggle_pause().unwrap();
+    assert!(runner.is_paused());
+    assert!(runner.restart(Some("unknown".into())).is_err());
+    assert!(runner.is_paused());
+    runner.restart(Some("snake2".into())).unwrap();
+    assert!(!runner.is_paused());
+    assert!(runner.is_running());
+    runner.restart(None).unwrap();
+    assert!(!runner.is_paused());
+    assert!(runner.is_running());
+    assert_eq!(runner.last_snapshot().score(), 0);
+}
+
+#[wasm_bindgen_test]
+fn share_code() {
+    use handmade_snake::{DirectionWrapper, ShareWrapper as Share};
+
+    let mut game = handmade_snake::create_game_from_level("###\n# #\n#h#\n#b#\n#f#\n###").unwrap();
+    game.tick(DirectionWrapper::Up);
+    game.tick(DirectionWrapper::Up);
+    assert!(!game.is_playback());
+
+    let share = Share::decode(&game.share_code()).unwrap();
+    assert!(share.has_replay());
+    let mut replayed = share.create_game();
+    assert!(replayed.is_playback());
+    assert_eq!(replayed.advance(10_000.), 2);
+    assert_eq!(
+        replayed.last_snapshot().game_over_reason().is_some(),
+        game.last_snapshot().game_over_reason().is_some()
+    );
+
+    // A replay restarts as a replay
+    let mut runner = handmade_snake::GameRunner::new(share.create_game());
+    runner.restart(None).unwrap();
+    assert!(runner.is_playback());
+
+    let level = Share::from_level(share.level()).unwrap();
+    assert!(!level.has_replay());
+    assert_eq!(Share::decode(&level.code()).unwrap().level(), share.level());
+    let crlf = Share::from_level("###\r\n# #\r\n#h#\r\n#b#\r\n#f#\r\n###".into()).unwrap();
+    assert_eq!(Share::decode(&crlf.code()).unwrap().level(), share.level());
+    let crlf_game = handmade_snake::create_game_from_level("hbf\r\n   \r\n").unwrap();
+    assert_eq!(
+        Share::decode(&crlf_game.share_code()).unwrap().level(),
+        "hbf\n   "
+    );
+    assert!(Share::from_level("#x#".into()).is_err());
+    assert!(Share::decode("not a code").is_err());
+}
+
+#[wasm_bindgen_test]
+fn level_editor_limits() {
+    use handmade_snake::LevelEditor;
+
+    let side = LevelEditor::max_side();
+    let row = "#".repeat(side);
+    assert!(LevelEditor::from_level(&format!("{}\nhbf", row)).is_ok());
+    assert!(LevelEditor::from_level(&format!("{}#\nhbf", row)).is_err());
+    assert!(LevelEditor::from_level(&vec!["hbf"; side + 1].join("\n")).is_err());
+}
@@ -2,155 +2,356 @@ import * as wasm from "handmade-snake"
 
 wasm.set_panic_hook()
 
-chooseGame()
-    .then(levelName => startGame(levelName))
+// Sprites copied from `bevy-snake/assets` by webpack
+const ASSETS_URL = 'assets'
+const CELL_SIZE = 24
+const EXAMPLE_CELL_SIZE = 12
+// Longest time a frame can advance a `WorkerGame`, like `GameRunner` does:
+// no frame runs while the tab is hidden
+const MAX_FRAME_MS = 250
 
+const params = new URLSearchParams(window.location.search)
+// `?spectate=ws://127.0.0.1:7879` follows a game streamed by snake-server
+const spectateUrl = params.get('spectate')
+// `#<code>` opens a level or a replay shared with a `Share` code
+const shareCode = window.location.hash.slice(1)
+// The page is not reloaded when only the fragment changes
+window.addEventListener('hashchange', () => window.location.reload())
+if (spectateUrl) {
+    spectate(spectateUrl)
+} else if (shareCode) {
+    openShared(shareCode)
+} else if (params.has('worker')) {
+    // `?worker` runs the game in a Web Worker
+    chooseGame()
+        .then(({ name, level }) => startWorkerGame(name, level))
+} else {
+    chooseGame()
+        .then(({ name, level }) => startGame(name, wasm.create_game_from_level(level)))
+}
+
+// Resolves with the name of the level, for the high scores, and the level
 function chooseGame() {
     const levels = wasm.levels()
     const div = document.querySelector('.choose-game')
 
     for (let levelName of Object.keys(levels)) {
-        const game = wasm.create_game(levelName)
-        const wallsObject = calculateWallsObject(game)
-
-        div.innerHTML += '<div class="example ' + levelName + '" data-level-name="' + levelName + '"><div>' + levelName + '</div><div class="board"></div></div>';
-
-        const el = document.querySelector('.choose-game .example.' + levelName + ' .board')
-        draw(game, el, wallsObject)
+        addExample(div, levelName)
     }
 
+    // Levels pasted by the player
+    const textarea = document.getElementById('custom-level')
+    const errorEl = document.getElementById('custom-level-error')
+    document.getElementById('add-level').addEventListener('click', function () {
+        try {
+            addCustomLevel(div, textarea.value)
+        } catch (e) {
+            errorEl.textContent = e.message
+            return
+        }
+        errorEl.textContent = ''
+        textarea.value = ''
+    })
+
     return new Promise((res) => {
-        div.addEventListener('click', function (ev) {
+        function play(name, level) {
             div.style.display = 'none'
-            let parent = ev.target.parentNode
-            let levelName = parent.getAttribute('data-level-name');
-            res(levelName)
+            document.querySelector('.custom-level').style.display = 'none'
+            document.querySelector('.editor').style.display = 'none'
+            res({ name, level })
+        }
+
+        div.addEventListener('click', function (ev) {
+            let parent = ev.target.closest('.example')
+            if (!parent) {
+                return
+            }
+            const levelName = parent.getAttribute('data-level-name')
+            play(levelName, wasm.levels()[levelName])
         })
+        setupEditor(div, play)
     })
     
 }
 
-let interval
-let direction = 0
-function startGame(level) {
-    const game = wasm.create_game(level)
-    const wallsObject = calculateWallsObject(game)
+let customLevels = 0
+// Add a level written by the player to the ones to choose
+function addCustomLevel(div, level) {
+    const levelName = `custom${customLevels + 1}`
+    wasm.register_level(levelName, level)
+    customLevels++
+    addExample(div, levelName)
+}
 
-    const gameEl = document.getElementById('game')
-    gameEl.style.display = "flex"
-    const gameBoardEl = document.getElementById('game-board')
-    gameBoardEl.style.display = "inherit"
-    const dieReasonEl = document.getElementById('die-reason')
-    const scoreEl = document.getElementById('score')
+// Draw again once the sprites arrive
+function drawWhenLoaded(renderer, draw) {
+    draw()
+    if (!renderer.is_loaded()) {
+        requestAnimationFrame(() => drawWhenLoaded(renderer, draw))
+    }
+}
 
-    const snapshot = game.last_snapshot()
+function addExample(div, levelName) {
+    const game = wasm.create_game(levelName)
 
-    let duration = snapshot.period_duration_ms()
-    interval = setInterval(run, duration)
+    const example = document.createElement('div')
+    example.className = 'example ' + levelName
+    example.setAttribute('data-level-name', levelName)
+    example.innerHTML = '<div>' + levelName + '</div><canvas class="board"></canvas>'
+    div.appendChild(example)
 
-    draw(game, gameBoardEl, wallsObject)
-    function run () {
-        console.log('run!')
+    const renderer = new wasm.CanvasRenderer(example.querySelector('.board'), EXAMPLE_CELL_SIZE, ASSETS_URL)
+    drawWhenLoaded(renderer, () => renderer.draw(game))
+}
 
-        game.tick(direction)
+// The painting and the checks are done by `LevelEditor`
+function setupEditor(div, play) {
+    const boardEl = document.getElementById('editor-board')
+    const brushEl = document.getElementById('editor-brush')
+    const widthEl = document.getElementById('editor-width')
+    const heightEl = document.getElementById('editor-height')
+    const loadEl = document.getElementById('editor-load')
+    const errorEl = document.getElementById('editor-error')
+    const levelEl = document.getElementById('editor-level')
+    const playEl = document.getElementById('editor-play')
+    const addEl = document.getElementById('editor-add')
+    const shareEl = document.getElementById('editor-share')
 
-        draw(game, gameBoardEl, wallsObject)
-        
-        let snapshot = game.last_snapshot();
-        let score = snapshot.score();
-        scoreEl.textContent = `score: ${score}`
+    // The same limit as `LevelEditor`
+    widthEl.max = heightEl.max = wasm.LevelEditor.max_side()
 
-        let reason = snapshot.get_game_over_reason();
-        if (reason) {
-            dieReasonEl.textContent += reason;
-            console.log('ENDED!')
-            clearInterval(interval)
-            return
+    const renderer = new wasm.CanvasRenderer(boardEl, CELL_SIZE, ASSETS_URL)
+    let editor = new wasm.LevelEditor(Number(widthEl.value), Number(heightEl.value))
+
+    function update() {
+        drawWhenLoaded(renderer, () => renderer.draw_editor(editor))
+        const error = editor.error()
+        errorEl.textContent = error || ''
+        levelEl.value = editor.level()
+        playEl.disabled = addEl.disabled = error !== undefined
+        shareEl.style.display = error === undefined ? '' : 'none'
+        if (error === undefined) {
+            shareEl.href = shareUrl(wasm.Share.from_level(editor.level()).code())
         }
+    }
+    update()
 
-        let new_duration = snapshot.period_duration_ms()
-        if (duration !== new_duration) {
-            duration = new_duration
-            console.log('clear & setInterval!', duration)
-            clearInterval(interval)
-            interval = setInterval(run, duration)
+    let painting = false
+    function paint(ev) {
+        const size = renderer.cell_size()
+        editor.paint(Math.floor(ev.offsetX / size), Math.floor(ev.offsetY / size), wasm.Cell[brushEl.value])
+        update()
+    }
+    boardEl.addEventListener('pointerdown', ev => {
+        painting = true
+        boardEl.setPointerCapture(ev.pointerId)
+        paint(ev)
+    })
+    boardEl.addEventListener('pointermove', ev => painting && paint(ev))
+    boardEl.addEventListener('pointerup', () => painting = false)
+
+    document.getElementById('editor-resize').addEventListener('click', () => {
+        editor.resize(Number(widthEl.value), Number(heightEl.value))
+        update()
+    })
+
+    // The levels are read when the list opens: the player may add some
+    function fillLevels() {
+        loadEl.innerHTML = '<option value="">Edit a level</option>'
+        for (const levelName of Object.keys(wasm.levels())) {
+            const option = document.createElement('option')
+            option.value = option.textContent = levelName
+            loadEl.appendChild(option)
         }
     }
+    fillLevels()
+    loadEl.addEventListener('focus', fillLevels)
+    loadEl.addEventListener('change', () => {
+        const level = wasm.levels()[loadEl.value]
+        if (level === undefined) {
+            return
+        }
+        let loaded
+        try {
+            loaded = wasm.LevelEditor.from_level(level)
+        } catch (e) {
+            errorEl.textContent = e.message
+            return
+        }
+        editor.free()
+        editor = loaded
+        widthEl.value = editor.width()
+        heightEl.value = editor.height()
+        update()
+    })
+
+    playEl.addEventListener('click', () => {
+        play('edited', editor.level())
+    })
+    addEl.addEventListener('click', () => addCustomLevel(div, editor.level()))
 }
 
-function calculateWallsObject(game) {
-    const walls = game.walls()
-    const wallsObject = {}
-    for (let i = 0; i < walls.length; i += 2) {
-        let x = walls[i]
-        let y = walls[i + 1]
-        wallsObject[`${x}-${y}`] = true
+// A link to the page opening `code`
+function shareUrl(code) {
+    return `${window.location.pathname}${window.location.search}#${code}`
+}
+
+function openShared(code) {
+    let share
+    try {
+        share = wasm.Share.decode(code)
+    } catch (e) {
+        console.error('Invalid share code', e)
+        chooseGame()
+            .then(({ name, level }) => startGame(name, wasm.create_game_from_level(level)))
+        return
     }
 
-    return wallsObject
+    startGame('shared', share.create_game())
 }
 
-function draw(game, el, wallsObject) {
-    const dim = game.dim()
-    const snapshot = game.last_snapshot()
-    const width = dim[0]
-    const height = dim[1]
+// `levelName` keeps the high scores
+function startGame(levelName, game) {
 
-    let snake = snapshot.snake()
-    let food = snapshot.food()
+    const gameEl = document.getElementById('game')
+    gameEl.style.display = "flex"
+    const gameBoardEl = document.getElementById('game-board')
+    gameBoardEl.style.display = "inherit"
+    const dieReasonEl = document.getElementById('die-reason')
+    const scoreEl = document.getElementById('score')
+    const shareEl = document.getElementById('share')
 
-    let head_x = snake[0]
-    let head_y = snake[1]
+    // The runner ticks the game and draws it on every animation frame
+    const runner = new wasm.GameRunner(game)
+    runner.set_renderer(new wasm.CanvasRenderer(gameBoardEl, CELL_SIZE, ASSETS_URL))
+    // Arrows, WASD, swipes on the board and gamepads
+    runner.set_controls(new wasm.Controls(gameBoardEl))
+    runner.on_score(score => {
+        scoreEl.textContent = `score: ${score}`
+    })
+    runner.on_game_over(reason => {
+        dieReasonEl.textContent += reason;
+        if (runner.is_playback()) {
+            dieReasonEl.textContent += ' - End of the replay'
+            return
+        }
+        shareEl.href = shareUrl(runner.share_code())
+        shareEl.style.display = ''
+        const rank = runner.save_score(levelName, 'player')
+        if (rank === 0) {
+            dieReasonEl.textContent += ' - New record!'
+        } else if (rank !== null) {
+            dieReasonEl.textContent += ` - High score #${rank + 1}`
+        }
+        console.log('ENDED!')
+    })
+    runner.start()
 
-    const body = []
-    for (let i = 2; i < snake.length; i += 2) {
-        let x = snake[i]
-        let y = snake[i + 1]
-        body.push(`${x}-${y}`)
+    const pauseEl = document.getElementById('pause')
+    const restartEl = document.getElementById('restart')
+    function togglePause() {
+        runner.toggle_pause()
+        pauseEl.textContent = runner.is_paused() ? 'Resume' : 'Pause'
+    }
+    function restart() {
+        dieReasonEl.textContent = ''
+        shareEl.style.display = 'none'
+        pauseEl.textContent = 'Pause'
+        // The same level, or the same replay
+        runner.restart()
     }
+    pauseEl.addEventListener('click', togglePause)
+    restartEl.addEventListener('click', restart)
+    document.addEventListener('keydown', ev => {
+        if (ev.code === 'KeyP' || ev.code === 'Space') {
+            ev.preventDefault()
+            togglePause()
+        } else if (ev.code === 'KeyR') {
+            restart()
+        }
+    })
+}
 
-    let content = '';
-
-    for (let y = 0; y < height; y++) {
-        let line = "";
-        for (let x = 0; x < width; x ++) {
-            if (head_x === x && head_y === y) {
-                line += '🅾️'
-            } else if (body.includes(`${x}-${y}`)) {
-                line += '❎'
-            } else if (food[0] === x && food[1] === y) {
-                line += '🍒'
-            } else if (wallsObject[`${x}-${y}`]) {
-                line += '⬛'
-            } else {
-                line += '⬜'
+function startWorkerGame(levelName, level) {
+    const gameEl = document.getElementById('game')
+    gameEl.style.display = "flex"
+    const gameBoardEl = document.getElementById('game-board')
+    gameBoardEl.style.display = "inherit"
+    const dieReasonEl = document.getElementById('die-reason')
+    const scoreEl = document.getElementById('score')
+    const shareEl = document.getElementById('share')
+
+    const game = new wasm.WorkerGame(new Worker('worker.js'), level)
+    const renderer = new wasm.CanvasRenderer(gameBoardEl, CELL_SIZE, ASSETS_URL)
+    const controls = new wasm.Controls(gameBoardEl)
+
+    let over = false
+    game.on_error(e => console.error(e))
+    game.on_change(snapshot => {
+        renderer.draw_worker_game(game)
+        scoreEl.textContent = `score: ${snapshot.score}`
+
+        const reason = snapshot.game_over_reason
+        if (reason && !over) {
+            over = true
+            dieReasonEl.textContent += reason
+            shareEl.href = shareUrl(game.share_code())
+            shareEl.style.display = ''
+            const rank = game.save_score(levelName, 'player')
+            if (rank === 0) {
+                dieReasonEl.textContent += ' - New record!'
+            } else if (rank !== null) {
+                dieReasonEl.textContent += ` - High score #${rank + 1}`
             }
         }
-        content = line + "\n" + content;
-    }
+    })
+
+    // The page only sends the input and the time: the worker ticks
+    let lastTimestamp = null
+    requestAnimationFrame(run)
+    function run(timestamp) {
+        const direction = controls.next_direction()
+        if (direction !== undefined) {
+            game.set_direction(direction)
+        }
+        const elapsed = lastTimestamp === null ? 0 : timestamp - lastTimestamp
+        game.advance(Math.min(Math.max(elapsed, 0), MAX_FRAME_MS))
+        lastTimestamp = timestamp
 
-    el.innerHTML = content
+        if (!over) {
+            requestAnimationFrame(run)
+        }
+    }
 }
 
-document.onkeydown = checkKey;
+function spectate(url) {
+    const spectator = wasm.create_spectator()
+
+    document.querySelector('.choose-game').style.display = 'none'
+    document.querySelector('.custom-level').style.display = 'none'
+    document.querySelector('.editor').style.display = 'none'
+    const gameEl = document.getElementById('game')
+    gameEl.style.display = "flex"
+    const gameBoardEl = document.getElementById('game-board')
+    gameBoardEl.style.display = "inherit"
+    const dieReasonEl = document.getElementById('die-reason')
+    const scoreEl = document.getElementById('score')
+    const renderer = new wasm.CanvasRenderer(gameBoardEl, CELL_SIZE, ASSETS_URL)
+
+    const socket = new WebSocket(url)
+    socket.addEventListener('message', function (ev) {
+        spectator.apply(ev.data)
 
-function checkKey(e) {
-    e = e || window.event;
+        const snapshot = spectator.last_snapshot()
+        if (!snapshot) {
+            return
+        }
+        renderer.draw_spectator(spectator)
 
-    // up arrow
-    if (e.keyCode == '38') {
-        direction = 0
-    }
-    // down arrow
-    else if (e.keyCode == '40') {
-        direction = 1
-    }
-    // left arrow
-    else if (e.keyCode == '37') {
-        direction = 2
-    }
-    // right arrow
-    else if (e.keyCode == '39') {
-        direction = 3
-    }
-}
\ No newline at end of file
+        scoreEl.textContent = `score: ${snapshot.score}`
+        dieReasonEl.textContent = snapshot.game_over_reason || ''
+    })
+    socket.addEventListener('close', function () {
+        dieReasonEl.textContent += ' - Stream ended'
+    })
+}
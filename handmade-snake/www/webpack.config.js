@@ -1,7 +1,7 @@
 const CopyWebpackPlugin = require("copy-webpack-plugin");
 const path = require('path');
 
-module.exports = {
+module.exports = [{
   entry: "./bootstrap.js",
   output: {
     path: path.resolve(__dirname, "dist"),
@@ -9,6 +9,19 @@ module.exports = {
   },
   mode: "development",
   plugins: [
-    new CopyWebpackPlugin(['index.html'])
+    new CopyWebpackPlugin([
+      'index.html',
+      // The sprites drawn by `CanvasRenderer`
+      { from: '../../bevy-snake/assets/*.png', to: 'assets', flatten: true },
+    ])
   ],
-};
+}, {
+  // `?worker` runs the game here
+  entry: "./worker.js",
+  target: "webworker",
+  output: {
+    path: path.resolve(__dirname, "dist"),
+    filename: "worker.js",
+  },
+  mode: "development",
+}];
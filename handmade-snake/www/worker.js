@@ -0,0 +1,14 @@
+// Runs the games of `WorkerGame`: see `start_worker` in `src/worker.rs`.
+// The commands sent while the wasm loads wait here.
+const pending = []
+self.onmessage = ev => pending.push(ev)
+
+import("handmade-snake")
+    .then(wasm => {
+        wasm.set_panic_hook()
+        wasm.start_worker()
+        for (const ev of pending) {
+            self.onmessage(ev)
+        }
+    })
+    .catch(e => console.error("Error starting the worker:", e))
@@ -0,0 +1,16 @@
+use std::env;
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    // Generated in `OUT_DIR`: `tests/c.rs` checks that `include/snake.h`
+    // is the same
+    let dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let header = format!("{}/snake.h", env::var("OUT_DIR").unwrap());
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", dir)).unwrap();
+    cbindgen::generate_with_config(&dir, config)
+        .expect("Unable to generate the C header")
+        .write_to_file(&header);
+    println!("cargo:rustc-env=SNAKE_FFI_HEADER={}", header);
+}
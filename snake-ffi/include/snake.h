@@ -0,0 +1,125 @@
+#ifndef SNAKE_H
+#define SNAKE_H
+
+/* Generated by cbindgen from snake-ffi/src/lib.rs: do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum SnakeDirection {
+  SNAKE_DIRECTION_UP,
+  SNAKE_DIRECTION_DOWN,
+  SNAKE_DIRECTION_LEFT,
+  SNAKE_DIRECTION_RIGHT,
+} SnakeDirection;
+
+/**
+ * A game, only used through a pointer
+ */
+typedef struct SnakeGame SnakeGame;
+
+/**
+ * `y` grows upwards
+ */
+typedef struct SnakePoint {
+  size_t x;
+  size_t y;
+} SnakePoint;
+
+/**
+ * The state after the last tick. The cells of the snake are read with
+ * [`snake_game_snake`].
+ */
+typedef struct SnakeSnapshot {
+  bool on_food;
+  bool on_wall;
+  bool eat_itself;
+  struct SnakePoint food;
+  struct SnakePoint head;
+  /**
+   * Cells of the snake, head included
+   */
+  size_t length;
+  size_t score;
+  uint64_t period_ms;
+  uint64_t elapsed_ms;
+} SnakeSnapshot;
+
+/**
+ * Parse a level, like `"###\n#h#\n#b#\n#f#\n###"`, and place the food
+ * starting from `seed`.
+ * Returns `NULL` if the level is invalid.
+ *
+ * # Safety
+ *
+ * `level` must be a valid nul terminated string
+ */
+struct SnakeGame *snake_game_new(const char *level, uint64_t seed);
+
+/**
+ * # Safety
+ *
+ * `game` must come from [`snake_game_new`] and not be used afterwards.
+ * `NULL` is ignored.
+ */
+void snake_game_free(struct SnakeGame *game);
+
+/**
+ * Move the snake one cell towards `direction`
+ *
+ * # Safety
+ *
+ * `game` must come from [`snake_game_new`]
+ */
+void snake_game_tick(struct SnakeGame *game, enum SnakeDirection direction);
+
+/**
+ * # Safety
+ *
+ * `game` must come from [`snake_game_new`]
+ */
+bool snake_game_is_over(const struct SnakeGame *game);
+
+/**
+ * # Safety
+ *
+ * `game` must come from [`snake_game_new`]
+ */
+struct SnakeSnapshot snake_game_snapshot(const struct SnakeGame *game);
+
+/**
+ * # Safety
+ *
+ * `game` must come from [`snake_game_new`], `width` and `height` must be
+ * valid pointers
+ */
+void snake_game_dim(const struct SnakeGame *game, size_t *width, size_t *height);
+
+/**
+ * Copy the cells of the snake, head first, in `out`.
+ * Returns the number of cells: call it with `NULL` to know the size of the
+ * buffer.
+ *
+ * # Safety
+ *
+ * `game` must come from [`snake_game_new`], `out` must be `NULL` or point
+ * to `len` points
+ */
+size_t snake_game_snake(const struct SnakeGame *game, struct SnakePoint *out, size_t len);
+
+/**
+ * Copy the walls in `out`.
+ * Returns the number of walls: call it with `NULL` to know the size of the
+ * buffer.
+ *
+ * # Safety
+ *
+ * `game` must come from [`snake_game_new`], `out` must be `NULL` or point
+ * to `len` points
+ */
+size_t snake_game_walls(const struct SnakeGame *game, struct SnakePoint *out, size_t len);
+
+#endif /* SNAKE_H */
@@ -0,0 +1,240 @@
+//! C ABI of the `snake` engine. The header is `include/snake.h`, generated
+//! by the build script in `OUT_DIR`: the tests check that it is up to date.
+//!
+//! A game is an opaque handle: create it with [`snake_game_new`] and give it
+//! back to [`snake_game_free`]. Lists of points are copied into buffers owned
+//! by the caller.
+
+use std::{ffi::CStr, os::raw::c_char, ptr, slice};
+
+use snake::{Direction, Game, Point};
+
+/// A game, only used through a pointer
+pub struct SnakeGame(Game);
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnakeDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl From<SnakeDirection> for Direction {
+    fn from(direction: SnakeDirection) -> Self {
+        match direction {
+            SnakeDirection::Up => Direction::Up,
+            SnakeDirection::Down => Direction::Down,
+            SnakeDirection::Left => Direction::Left,
+            SnakeDirection::Right => Direction::Right,
+        }
+    }
+}
+
+/// `y` grows upwards
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnakePoint {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl From<Point> for SnakePoint {
+    fn from(p: Point) -> Self {
+        Self { x: p.x, y: p.y }
+    }
+}
+
+/// The state after the last tick. The cells of the snake are read with
+/// [`snake_game_snake`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnakeSnapshot {
+    pub on_food: bool,
+    pub on_wall: bool,
+    pub eat_itself: bool,
+    pub food: SnakePoint,
+    pub head: SnakePoint,
+    /// Cells of the snake, head included
+    pub length: usize,
+    pub score: usize,
+    pub period_ms: u64,
+    pub elapsed_ms: u64,
+}
+
+/// Parse a level, like `"###\n#h#\n#b#\n#f#\n###"`, and place the food
+/// starting from `seed`.
+/// Returns `NULL` if the level is invalid.
+///
+/// # Safety
+///
+/// `level` must be a valid nul terminated string
+#[no_mangle]
+pub unsafe extern "C" fn snake_game_new(level: *const c_char, seed: u64) -> *mut SnakeGame {
+    if level.is_null() {
+        return ptr::null_mut();
+    }
+    let level = match CStr::from_ptr(level).to_str() {
+        Ok(level) => level,
+        Err(_) => return ptr::null_mut(),
+    };
+    match level.parse::<Game>() {
+        Ok(mut game) => {
+            game.set_seed(seed);
+            Box::into_raw(Box::new(SnakeGame(game)))
+        }
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `game` must come from [`snake_game_new`] and not be used afterwards.
+/// `NULL` is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn snake_game_free(game: *mut SnakeGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// Move the snake one cell towards `direction`
+///
+/// # Safety
+///
+/// `game` must come from [`snake_game_new`]
+#[no_mangle]
+pub unsafe extern "C" fn snake_game_tick(game: *mut SnakeGame, direction: SnakeDirection) {
+    (*game).0.tick(direction.into());
+}
+
+/// # Safety
+///
+/// `game` must come from [`snake_game_new`]
+#[no_mangle]
+pub unsafe extern "C" fn snake_game_is_over(game: *const SnakeGame) -> bool {
+    (*game).0.is_over()
+}
+
+/// # Safety
+///
+/// `game` must come from [`snake_game_new`]
+#[no_mangle]
+pub unsafe extern "C" fn snake_game_snapshot(game: *const SnakeGame) -> SnakeSnapshot {
+    let snapshot = (*game).0.last_snapshot();
+    SnakeSnapshot {
+        on_food: snapshot.on_food,
+        on_wall: snapshot.on_wall,
+        eat_itself: snapshot.eat_itself,
+        food: snapshot.food_position.into(),
+        head: snapshot.snake[0].into(),
+        length: snapshot.snake.len(),
+        score: snapshot.score,
+        period_ms: snapshot.period_duration.as_millis() as u64,
+        elapsed_ms: snapshot.elapsed.as_millis() as u64,
+    }
+}
+
+/// # Safety
+///
+/// `game` must come from [`snake_game_new`], `width` and `height` must be
+/// valid pointers
+#[no_mangle]
+pub unsafe extern "C" fn snake_game_dim(
+    game: *const SnakeGame,
+    width: *mut usize,
+    height: *mut usize,
+) {
+    let (w, h) = (*game).0.dim();
+    *width = w;
+    *height = h;
+}
+
+/// Copy up to `len` points in `out`, returning how many there are in all
+unsafe fn copy_points(points: Vec<Point>, out: *mut SnakePoint, len: usize) -> usize {
+    if !out.is_null() {
+        let out = slice::from_raw_parts_mut(out, len);
+        for (o, p) in out.iter_mut().zip(&points) {
+            *o = (*p).into();
+        }
+    }
+    points.len()
+}
+
+/// Copy the cells of the snake, head first, in `out`.
+/// Returns the number of cells: call it with `NULL` to know the size of the
+/// buffer.
+///
+/// # Safety
+///
+/// `game` must come from [`snake_game_new`], `out` must be `NULL` or point
+/// to `len` points
+#[no_mangle]
+pub unsafe extern "C" fn snake_game_snake(
+    game: *const SnakeGame,
+    out: *mut SnakePoint,
+    len: usize,
+) -> usize {
+    copy_points((*game).0.last_snapshot().snake, out, len)
+}
+
+/// Copy the walls in `out`.
+/// Returns the number of walls: call it with `NULL` to know the size of the
+/// buffer.
+///
+/// # Safety
+///
+/// `game` must come from [`snake_game_new`], `out` must be `NULL` or point
+/// to `len` points
+#[no_mangle]
+pub unsafe extern "C" fn snake_game_walls(
+    game: *const SnakeGame,
+    out: *mut SnakePoint,
+    len: usize,
+) -> usize {
+    copy_points((*game).0.walls(), out, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::CString, ptr};
+
+    use snake::SNAKE_1;
+
+    use super::*;
+
+    #[test]
+    fn test_game() {
+        let level = CString::new(SNAKE_1).unwrap();
+        unsafe {
+            let game = snake_game_new(level.as_ptr(), 0);
+            assert!(!game.is_null());
+
+            let mut walls = vec![SnakePoint { x: 0, y: 0 }; 4];
+            assert_eq!(snake_game_walls(game, ptr::null_mut(), 0), 28);
+            assert_eq!(snake_game_walls(game, walls.as_mut_ptr(), walls.len()), 28);
+            assert_eq!(walls[0], SnakePoint { x: 0, y: 0 });
+
+            snake_game_tick(game, SnakeDirection::Up);
+            let snapshot = snake_game_snapshot(game);
+            assert_eq!(snapshot.head, SnakePoint { x: 4, y: 3 });
+            assert_eq!(snapshot.length, 2);
+            assert!(!snake_game_is_over(game));
+
+            let mut snake = [SnakePoint { x: 0, y: 0 }; 2];
+            assert_eq!(snake_game_snake(game, snake.as_mut_ptr(), 2), 2);
+            assert_eq!(snake[1], SnakePoint { x: 4, y: 2 });
+
+            snake_game_free(game);
+        }
+
+        let invalid = CString::new("#x#").unwrap();
+        let no_snake = CString::new("#f#").unwrap();
+        unsafe {
+            assert!(snake_game_new(invalid.as_ptr(), 0).is_null());
+            assert!(snake_game_new(no_snake.as_ptr(), 0).is_null());
+            assert!(snake_game_new(ptr::null(), 0).is_null());
+        }
+    }
+}
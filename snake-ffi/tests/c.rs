@@ -0,0 +1,68 @@
+//! Build `tests/test.c` against the shared library and run it
+
+use std::{env, fs, path::PathBuf, process::Command};
+
+/// The header is checked in for the C users: it must be the one generated
+/// by the build script
+#[test]
+fn test_header() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let generated = fs::read_to_string(env!("SNAKE_FFI_HEADER")).unwrap();
+    let checked_in = fs::read_to_string(manifest_dir.join("include/snake.h")).unwrap();
+    assert!(
+        generated == checked_in,
+        "include/snake.h is outdated: copy {} over it",
+        env!("SNAKE_FFI_HEADER")
+    );
+}
+
+#[test]
+fn test_c() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // <target>/<profile dir>/deps/c-<hash>: the library is in <profile dir>
+    let exe = env::current_exe().unwrap();
+    let lib_dir = exe.parent().unwrap().parent().unwrap();
+    let out = lib_dir.join("snake-ffi-test");
+
+    // The profile that built the test: `test` uses the `debug` dir of `dev`
+    let profile = match lib_dir.file_name().unwrap().to_str().unwrap() {
+        "debug" => "dev",
+        profile => profile,
+    };
+
+    // Integration tests link the rlib: build the shared library too
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--lib", "--profile", profile, "--manifest-path"])
+        .arg(manifest_dir.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(lib_dir.parent().unwrap())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+    let status = Command::new(cc)
+        .arg(manifest_dir.join("tests/test.c"))
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg("-L")
+        .arg(lib_dir)
+        .arg("-lsnake_ffi")
+        .arg("-o")
+        .arg(&out)
+        .status()
+        .expect("Unable to run the C compiler");
+    assert!(status.success());
+
+    let output = Command::new(&out)
+        .env("LD_LIBRARY_PATH", lib_dir)
+        .env("DYLD_LIBRARY_PATH", lib_dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
@@ -0,0 +1,61 @@
+/* Plays a game through the C ABI. Built and run by tests/c.rs */
+#include <assert.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "snake.h"
+
+static const char *LEVEL =
+    "##########\n"
+    "#   f    #\n"
+    "#   h    #\n"
+    "#   b    #\n"
+    "#        #\n"
+    "##########";
+
+int main(void) {
+    SnakeGame *game = snake_game_new(LEVEL, 42);
+    assert(game != NULL);
+
+    size_t width, height;
+    snake_game_dim(game, &width, &height);
+    assert(width == 10 && height == 6);
+
+    size_t walls_len = snake_game_walls(game, NULL, 0);
+    assert(walls_len == 28);
+    SnakePoint *walls = malloc(walls_len * sizeof(SnakePoint));
+    assert(snake_game_walls(game, walls, walls_len) == walls_len);
+    assert(walls[0].x == 0 && walls[0].y == 0);
+    free(walls);
+
+    /* Eat the food */
+    snake_game_tick(game, SNAKE_DIRECTION_UP);
+    SnakeSnapshot snapshot = snake_game_snapshot(game);
+    assert(snapshot.on_food);
+    assert(snapshot.score == 1);
+    assert(snapshot.head.x == 4 && snapshot.head.y == 4);
+    assert(!snake_game_is_over(game));
+
+    /* Grow while turning */
+    snake_game_tick(game, SNAKE_DIRECTION_LEFT);
+    snapshot = snake_game_snapshot(game);
+    assert(snapshot.length == 3);
+    SnakePoint snake[3];
+    assert(snake_game_snake(game, snake, 3) == 3);
+    assert(snake[0].x == 3 && snake[0].y == 4);
+
+    /* Into the wall */
+    snake_game_tick(game, SNAKE_DIRECTION_LEFT);
+    snake_game_tick(game, SNAKE_DIRECTION_LEFT);
+    snake_game_tick(game, SNAKE_DIRECTION_LEFT);
+    snapshot = snake_game_snapshot(game);
+    assert(snapshot.on_wall);
+    assert(snake_game_is_over(game));
+
+    snake_game_free(game);
+
+    assert(snake_game_new("#x#", 0) == NULL);
+
+    printf("ok\n");
+    return 0;
+}
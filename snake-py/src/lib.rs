@@ -0,0 +1,439 @@
+//! Python bindings of the `snake` engine, built with maturin.
+//!
+//! Boards become NumPy arrays of `uint8` cells, a row per line of the level:
+//! the first row is the top of the board.
+
+use numpy::{
+    ndarray::{Array1, Array2, Array3},
+    IntoPyArray, PyArray1, PyArray2, PyArray3,
+};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use snake::{campaign::Campaign, Point};
+
+pub const EMPTY: u8 = 0;
+pub const WALL: u8 = 1;
+pub const VOID: u8 = 2;
+pub const FOOD: u8 = 3;
+pub const BODY: u8 = 4;
+pub const HEAD: u8 = 5;
+
+#[pyclass(eq, eq_int)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl From<Direction> for snake::Direction {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Up => snake::Direction::Up,
+            Direction::Down => snake::Direction::Down,
+            Direction::Left => snake::Direction::Left,
+            Direction::Right => snake::Direction::Right,
+        }
+    }
+}
+
+/// A `Direction` or its index, as agents usually pick them
+#[derive(FromPyObject)]
+enum Action {
+    Direction(Direction),
+    Index(usize),
+}
+
+impl TryFrom<Action> for snake::Direction {
+    type Error = PyErr;
+
+    fn try_from(action: Action) -> PyResult<Self> {
+        let direction = match action {
+            Action::Direction(direction) => direction,
+            Action::Index(0) => Direction::Up,
+            Action::Index(1) => Direction::Down,
+            Action::Index(2) => Direction::Left,
+            Action::Index(3) => Direction::Right,
+            Action::Index(i) => {
+                return Err(PyValueError::new_err(format!("Invalid direction {}", i)))
+            }
+        };
+        Ok(direction.into())
+    }
+}
+
+/// The state after a tick
+#[pyclass(frozen, get_all)]
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub on_food: bool,
+    pub on_wall: bool,
+    pub eat_itself: bool,
+    /// `(x, y)` with `y` growing upwards
+    pub food: (usize, usize),
+    /// Head first
+    pub snake: Vec<(usize, usize)>,
+    pub score: usize,
+    /// Seconds
+    pub period: f64,
+    /// Seconds of game time
+    pub elapsed: f64,
+    pub game_over_reason: Option<&'static str>,
+    width: usize,
+    height: usize,
+    board: Vec<u8>,
+}
+
+#[pymethods]
+impl Snapshot {
+    /// The board as a `(height, width)` array of cells
+    fn to_numpy<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<u8>> {
+        Array2::from_shape_vec((self.height, self.width), self.board.clone())
+            .unwrap()
+            .into_pyarray(py)
+    }
+
+    /// The cells of the snake as a `(length, 2)` array of `x, y`
+    fn snake_to_numpy<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<usize>> {
+        let cells = self.snake.iter().flat_map(|(x, y)| [*x, *y]).collect();
+        Array2::from_shape_vec((self.snake.len(), 2), cells)
+            .unwrap()
+            .into_pyarray(py)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Snapshot(score={}, snake={:?}, food={:?}, game_over_reason={:?})",
+            self.score, self.snake, self.food, self.game_over_reason
+        )
+    }
+}
+
+fn to_tuple(p: &Point) -> (usize, usize) {
+    (p.x, p.y)
+}
+
+/// The cells of the board, the first row on top
+fn board(game: &snake::Game) -> Vec<u8> {
+    let (width, height) = game.dim();
+    let mut board = vec![EMPTY; width * height];
+    let snapshot = game.last_snapshot();
+
+    let mut set = |p: &Point, cell: u8| {
+        if p.x < width && p.y < height {
+            board[(height - 1 - p.y) * width + p.x] = cell;
+        }
+    };
+    for p in &game.voids() {
+        set(p, VOID);
+    }
+    for p in &game.walls() {
+        set(p, WALL);
+    }
+    set(&snapshot.food_position, FOOD);
+    for p in &snapshot.snake[1..] {
+        set(p, BODY);
+    }
+    set(&snapshot.snake[0], HEAD);
+
+    board
+}
+
+fn parse(level: &str, seed: Option<u64>) -> PyResult<snake::Game> {
+    let mut game: snake::Game = level.parse().map_err(PyValueError::new_err)?;
+    if let Some(seed) = seed {
+        game.set_seed(seed);
+    }
+    Ok(game)
+}
+
+#[pyclass]
+pub struct Game(snake::Game);
+
+#[pymethods]
+impl Game {
+    /// Parse a level. Without `seed` the food is placed randomly.
+    #[new]
+    #[pyo3(signature = (level, seed=None))]
+    fn new(level: &str, seed: Option<u64>) -> PyResult<Self> {
+        parse(level, seed).map(Self)
+    }
+
+    fn tick(&mut self, direction: Direction) {
+        self.0.tick(direction.into());
+    }
+
+    fn is_over(&self) -> bool {
+        self.0.is_over()
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let snapshot = self.0.last_snapshot();
+        let (width, height) = self.0.dim();
+        Snapshot {
+            on_food: snapshot.on_food,
+            on_wall: snapshot.on_wall,
+            eat_itself: snapshot.eat_itself,
+            food: to_tuple(&snapshot.food_position),
+            snake: snapshot.snake.iter().map(to_tuple).collect(),
+            score: snapshot.score,
+            period: snapshot.period_duration.as_secs_f64(),
+            elapsed: snapshot.elapsed.as_secs_f64(),
+            game_over_reason: snapshot.get_game_over_reason(),
+            width,
+            height,
+            board: board(&self.0),
+        }
+    }
+
+    /// `(width, height)`
+    fn dim(&self) -> (usize, usize) {
+        self.0.dim()
+    }
+
+    fn walls(&self) -> Vec<(usize, usize)> {
+        self.0.walls().iter().map(to_tuple).collect()
+    }
+
+    /// The current board as a level
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+/// `n` games of the same level played at once, as reinforcement learning
+/// agents like. A game over starts a new game with the next seed.
+#[pyclass]
+pub struct VecEnv {
+    level: String,
+    games: Vec<snake::Game>,
+    /// Seed of the next game started
+    seed: u64,
+}
+
+impl VecEnv {
+    fn new_game(&mut self) -> snake::Game {
+        let game = parse(&self.level, Some(self.seed)).unwrap();
+        self.seed += 1;
+        game
+    }
+
+    fn observations<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray3<u8>> {
+        let (width, height) = self.games[0].dim();
+        let cells = self.games.iter().flat_map(board).collect();
+        Array3::from_shape_vec((self.games.len(), height, width), cells)
+            .unwrap()
+            .into_pyarray(py)
+    }
+}
+
+#[pymethods]
+impl VecEnv {
+    #[new]
+    #[pyo3(signature = (level, n, seed=0))]
+    fn new(level: &str, n: usize, seed: u64) -> PyResult<Self> {
+        if n == 0 {
+            return Err(PyValueError::new_err("At least a game is needed"));
+        }
+        // Fail here on an invalid level
+        parse(level, None)?;
+
+        let mut env = Self {
+            level: level.to_owned(),
+            games: vec![],
+            seed,
+        };
+        env.games = (0..n).map(|_| env.new_game()).collect();
+        Ok(env)
+    }
+
+    fn __len__(&self) -> usize {
+        self.games.len()
+    }
+
+    /// Start every game again. Returns the `(n, height, width)` observations.
+    fn reset<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray3<u8>> {
+        for i in 0..self.games.len() {
+            self.games[i] = self.new_game();
+        }
+        self.observations(py)
+    }
+
+    /// Tick every game with its action, a `Direction` or its index.
+    /// Returns the observations, the rewards (`1` eating, `-1` dying) and
+    /// which games ended: those start again.
+    #[allow(clippy::type_complexity)]
+    fn step<'py>(
+        &mut self,
+        py: Python<'py>,
+        actions: Vec<Action>,
+    ) -> PyResult<(
+        Bound<'py, PyArray3<u8>>,
+        Bound<'py, PyArray1<f32>>,
+        Bound<'py, PyArray1<bool>>,
+    )> {
+        if actions.len() != self.games.len() {
+            return Err(PyValueError::new_err(format!(
+                "Expected {} actions, got {}",
+                self.games.len(),
+                actions.len()
+            )));
+        }
+        let directions = actions
+            .into_iter()
+            .map(snake::Direction::try_from)
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let mut rewards = Array1::zeros(self.games.len());
+        let mut dones = Array1::from_elem(self.games.len(), false);
+        for (i, direction) in directions.into_iter().enumerate() {
+            self.games[i].tick(direction);
+
+            let snapshot = self.games[i].last_snapshot();
+            if snapshot.on_food {
+                rewards[i] = 1.;
+            }
+            if snapshot.get_game_over_reason().is_some() {
+                rewards[i] = -1.;
+                dones[i] = true;
+                self.games[i] = self.new_game();
+            }
+        }
+
+        Ok((
+            self.observations(py),
+            rewards.into_pyarray(py),
+            dones.into_pyarray(py),
+        ))
+    }
+}
+
+/// The levels of the campaign by name
+#[pyfunction]
+fn levels() -> Vec<(&'static str, &'static str)> {
+    Campaign::default()
+        .levels()
+        .iter()
+        .map(|l| (l.name, l.board))
+        .collect()
+}
+
+#[pymodule]
+fn snake_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Direction>()?;
+    m.add_class::<Snapshot>()?;
+    m.add_class::<Game>()?;
+    m.add_class::<VecEnv>()?;
+    m.add_function(wrap_pyfunction!(levels, m)?)?;
+
+    m.add("EMPTY", EMPTY)?;
+    m.add("WALL", WALL)?;
+    m.add("VOID", VOID)?;
+    m.add("FOOD", FOOD)?;
+    m.add("BODY", BODY)?;
+    m.add("HEAD", HEAD)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use pyo3::{prelude::*, types::PyDict};
+
+    /// Run `code` with the module imported as `snake`
+    fn run(code: &str) -> PyResult<()> {
+        Python::attach(|py| {
+            let module = PyModule::new(py, "snake")?;
+            super::snake_py(&module)?;
+            let globals = PyDict::new(py);
+            globals.set_item("snake", module)?;
+            py.run(&CString::new(code).unwrap(), Some(&globals), None)
+        })
+    }
+
+    fn has_numpy() -> bool {
+        let found = Python::attach(|py| py.import("numpy").is_ok());
+        if !found {
+            eprintln!("NumPy is not installed: skipped");
+        }
+        found
+    }
+
+    #[test]
+    fn test_game() {
+        run(r##"
+levels = dict(snake.levels())
+game = snake.Game(levels["snake1"], seed=3)
+assert game.dim() == (10, 6)
+assert len(game.walls()) == 28
+assert str(game) == levels["snake1"]
+
+game.tick(snake.Direction.Up)
+snapshot = game.snapshot()
+assert snapshot.snake[0] == (4, 3)
+assert snapshot.score == 0
+assert snapshot.period == 1.0
+assert snapshot.game_over_reason is None
+
+for _ in range(4):
+    game.tick(snake.Direction.Left)
+assert game.is_over()
+assert game.snapshot().game_over_reason == "On Wall"
+
+try:
+    snake.Game("#x#")
+    assert False
+except ValueError as e:
+    assert "Invalid char" in str(e)
+"##)
+        .unwrap();
+    }
+
+    #[test]
+    fn test_numpy() {
+        if !has_numpy() {
+            return;
+        }
+        run(r#"
+levels = dict(snake.levels())
+board = snake.Game(levels["snake1"]).snapshot().to_numpy()
+assert board.shape == (6, 10)
+assert board[3, 4] == snake.HEAD
+assert board[4, 4] == snake.BODY
+assert board[0, 0] == snake.WALL
+
+env = snake.VecEnv(levels["snake1"], 4)
+assert env.reset().shape == (4, 6, 10)
+for _ in range(4):
+    obs, rewards, dones = env.step([2, 2, 2, snake.Direction.Right])
+assert dones.tolist() == [True, True, True, False]
+assert rewards.tolist() == [-1, -1, -1, 0]
+assert obs.shape == (4, 6, 10)
+"#)
+        .unwrap();
+    }
+
+    #[test]
+    fn test_vec_env() {
+        run(r#"
+levels = dict(snake.levels())
+try:
+    snake.VecEnv(levels["snake1"], 0)
+    assert False
+except ValueError:
+    pass
+
+env = snake.VecEnv(levels["snake1"], 2)
+assert len(env) == 2
+for actions in [[0], [0, 4]]:
+    try:
+        env.step(actions)
+        assert False
+    except ValueError:
+        pass
+"#)
+        .unwrap();
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+
+use snake::{
+    topology::{Topology, Torus},
+    Direction, Game, Point,
+};
+
+use crate::protocol::{SnakeState, State};
+
+/// A board shared by many snakes, all moved by the same tick.
+/// The edges of the board wrap around, like in [`Game`].
+pub struct Arena {
+    width: usize,
+    height: usize,
+    walls: Vec<Point>,
+    voids: Vec<Point>,
+    food: Point,
+    snakes: Vec<SnakeState>,
+    /// The direction taken previously by every player
+    directions: BTreeMap<usize, Direction>,
+    tick: u64,
+    rng: fastrand::Rng,
+}
+
+impl Arena {
+    /// The walls, the voids and the food of `level`. The snake of the level
+    /// is ignored: every player gets its own.
+    pub fn new(level: &str, seed: u64) -> Result<Self, String> {
+        let game: Game = level.parse()?;
+        let (width, height) = game.dim();
+
+        Ok(Self {
+            width,
+            height,
+            walls: game.walls(),
+            voids: game.voids(),
+            food: game.last_snapshot().food_position,
+            snakes: vec![],
+            directions: BTreeMap::new(),
+            tick: 0,
+            rng: fastrand::Rng::with_seed(seed),
+        })
+    }
+
+    /// Place a new snake, head and one piece of body, on a free spot
+    pub fn add_player(&mut self, player: usize) -> Result<(), String> {
+        let candidates: Vec<(Point, Point)> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Point { x, y }))
+            .filter_map(|head| {
+                let body = self.next_cell(head, Direction::Down)?;
+                let up = self.next_cell(head, Direction::Up)?;
+                if [head, body, up].iter().all(|p| self.is_free(*p)) {
+                    Some((head, body))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        if candidates.is_empty() {
+            return Err("No room for a new snake".to_owned());
+        }
+        let (head, body) = candidates[self.rng.usize(..candidates.len())];
+
+        self.snakes.push(SnakeState {
+            player,
+            alive: true,
+            score: 0,
+            body: vec![head, body],
+        });
+        self.directions.insert(player, Direction::Up);
+
+        Ok(())
+    }
+
+    /// The snake of the player leaves the board
+    pub fn remove_player(&mut self, player: usize) {
+        self.snakes.retain(|s| s.player != player);
+        self.directions.remove(&player);
+    }
+
+    /// Move every snake alive. Players without a direction keep going the
+    /// way they were going.
+    pub fn tick(&mut self, directions: &BTreeMap<usize, Direction>) {
+        let mut food_eaten = false;
+
+        for snake in self.snakes.iter_mut().filter(|s| s.alive) {
+            let previous = self.directions[&snake.player];
+            let direction = match directions.get(&snake.player) {
+                Some(&direction) if !Torus::is_opposite(direction, previous) => direction,
+                _ => previous,
+            };
+            self.directions.insert(snake.player, direction);
+
+            let head = snake.body[0];
+            let new_head =
+                next_cell(&self.voids, self.width, self.height, head, direction).unwrap_or(head);
+
+            snake.body.insert(0, new_head);
+            if new_head == self.food {
+                snake.score += 1;
+                food_eaten = true;
+            } else {
+                snake.body.pop();
+            }
+        }
+
+        // Who hit something: a wall, a body or another head
+        let dead: Vec<usize> = self
+            .snakes
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.alive)
+            .filter(|(i, s)| {
+                let head = s.body[0];
+                self.walls.contains(&head)
+                    || self.snakes.iter().enumerate().any(|(j, other)| {
+                        let skip = if *i == j { 1 } else { 0 };
+                        other.body.iter().skip(skip).any(|p| *p == head)
+                    })
+            })
+            .map(|(i, _)| i)
+            .collect();
+        for i in dead {
+            self.snakes[i].alive = false;
+            self.snakes[i].body.clear();
+        }
+
+        if food_eaten {
+            self.generate_new_food_position();
+        }
+
+        self.tick += 1;
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.snakes.iter().all(|s| !s.alive)
+    }
+
+    pub fn tick_count(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn state(&self) -> State {
+        State {
+            tick: self.tick,
+            food: self.food,
+            snakes: self.snakes.clone(),
+        }
+    }
+
+    fn next_cell(&self, point: Point, direction: Direction) -> Option<Point> {
+        next_cell(&self.voids, self.width, self.height, point, direction)
+    }
+
+    fn is_free(&self, point: Point) -> bool {
+        !self.walls.contains(&point)
+            && !self.voids.contains(&point)
+            && point != self.food
+            && !self.snakes.iter().any(|s| s.body.contains(&point))
+    }
+
+    fn generate_new_food_position(&mut self) {
+        let free: Vec<Point> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Point { x, y }))
+            .filter(|p| self.is_free(*p))
+            .collect();
+
+        // A full board keeps the food where it is
+        if !free.is_empty() {
+            self.food = free[self.rng.usize(..free.len())];
+        }
+    }
+}
+
+/// Like [`Game`], the snake jumps over the voids
+fn next_cell(
+    voids: &[Point],
+    width: usize,
+    height: usize,
+    mut point: Point,
+    direction: Direction,
+) -> Option<Point> {
+    for _ in 0..width * height {
+        point = Torus.neighbour(point, direction, width, height)?;
+        if !voids.contains(&point) {
+            return Some(point);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use snake::{Direction, Point, SNAKE_1, SNAKE_2};
+
+    use super::Arena;
+
+    fn p(x: usize, y: usize) -> Point {
+        Point { x, y }
+    }
+
+    #[test]
+    fn test_add_player() {
+        let mut arena = Arena::new(SNAKE_1, 1).unwrap();
+        for player in 0..4 {
+            arena.add_player(player).unwrap();
+        }
+
+        let state = arena.state();
+        assert_eq!(state.snakes.len(), 4);
+        for (i, snake) in state.snakes.iter().enumerate() {
+            assert_eq!(snake.body.len(), 2);
+            for other in &state.snakes[i + 1..] {
+                assert!(snake.body.iter().all(|p| !other.body.contains(p)));
+            }
+        }
+
+        arena.remove_player(2);
+        assert_eq!(arena.state().snakes.len(), 3);
+    }
+
+    #[test]
+    fn test_tick() {
+        let mut arena = Arena::new(SNAKE_2, 1).unwrap();
+        arena.add_player(0).unwrap();
+        arena.add_player(1).unwrap();
+        arena.food = p(0, 0);
+        arena.snakes[0].body = vec![p(2, 2), p(2, 1)];
+        arena.snakes[1].body = vec![p(6, 2), p(6, 1)];
+
+        let mut directions = BTreeMap::new();
+        directions.insert(0, Direction::Right);
+        // Cannot turn back: keeps going up
+        directions.insert(1, Direction::Down);
+        arena.tick(&directions);
+
+        let state = arena.state();
+        assert_eq!(state.tick, 1);
+        assert_eq!(state.snakes[0].body, vec![p(3, 2), p(2, 2)]);
+        assert_eq!(state.snakes[1].body, vec![p(6, 3), p(6, 2)]);
+
+        // No direction: the same as before
+        arena.tick(&BTreeMap::new());
+        let state = arena.state();
+        assert_eq!(state.snakes[0].body[0], p(4, 2));
+        assert_eq!(state.snakes[1].body[0], p(6, 4));
+    }
+
+    #[test]
+    fn test_collisions() {
+        let mut arena = Arena::new(SNAKE_2, 1).unwrap();
+        arena.add_player(0).unwrap();
+        arena.add_player(1).unwrap();
+        arena.add_player(2).unwrap();
+        arena.food = p(9, 3);
+        // 0 runs into the body of 1, 2 eats
+        arena.snakes[0].body = vec![p(2, 2), p(1, 2)];
+        arena.snakes[1].body = vec![p(3, 3), p(3, 2), p(3, 1)];
+        arena.snakes[2].body = vec![p(9, 2), p(9, 1)];
+
+        let mut directions = BTreeMap::new();
+        directions.insert(0, Direction::Right);
+        arena.tick(&directions);
+
+        let state = arena.state();
+        assert!(!state.snakes[0].alive);
+        assert!(state.snakes[0].body.is_empty());
+        assert!(state.snakes[1].alive);
+        assert!(state.snakes[2].alive);
+        assert_eq!(state.snakes[2].score, 1);
+        assert_eq!(state.snakes[2].body, vec![p(9, 3), p(9, 2), p(9, 1)]);
+        assert_ne!(state.food, p(9, 3));
+        assert!(!arena.is_over());
+
+        // Head on: both reach 5,0
+        arena.snakes[1].body = vec![p(5, 5), p(5, 4)];
+        arena.snakes[2].body = vec![p(6, 0), p(7, 0)];
+        let mut directions = BTreeMap::new();
+        directions.insert(1, Direction::Up);
+        directions.insert(2, Direction::Left);
+        arena.tick(&directions);
+        assert!(arena.is_over());
+    }
+}
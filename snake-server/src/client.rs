@@ -0,0 +1,94 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+};
+
+use snake::Direction;
+
+use crate::protocol::{ClientMessage, ServerMessage, VERSION};
+
+/// A player connected to a [`Server`](crate::Server)
+pub struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    player: usize,
+}
+
+impl Client {
+    /// Connect and say hello
+    pub fn connect(addr: impl ToSocketAddrs, name: &str) -> Result<Self, String> {
+        let writer = TcpStream::connect(addr).map_err(|e| e.to_string())?;
+        let reader = BufReader::new(writer.try_clone().map_err(|e| e.to_string())?);
+        let mut client = Self {
+            reader,
+            writer,
+            player: 0,
+        };
+
+        client.send(&ClientMessage::Hello {
+            version: VERSION,
+            name: name.to_owned(),
+        })?;
+        match client.recv()? {
+            ServerMessage::Welcome { player, .. } => client.player = player,
+            message => return Err(unexpected(message)),
+        }
+
+        Ok(client)
+    }
+
+    /// The id given by the server
+    pub fn player(&self) -> usize {
+        self.player
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> Result<(), String> {
+        writeln!(self.writer, "{}", message).map_err(|e| e.to_string())
+    }
+
+    /// Wait for the next message of the server
+    pub fn recv(&mut self) -> Result<ServerMessage, String> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => Err("Disconnected".to_owned()),
+            Ok(_) => line.trim_end().parse(),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Create a room playing the campaign level and join it.
+    /// Returns the id of the room.
+    pub fn create(&mut self, level: &str) -> Result<usize, String> {
+        self.send(&ClientMessage::Create {
+            level: level.to_owned(),
+        })?;
+        self.wait_room()
+    }
+
+    pub fn join(&mut self, room: usize) -> Result<(), String> {
+        self.send(&ClientMessage::Join { room })?;
+        self.wait_room().map(|_| ())
+    }
+
+    pub fn start(&mut self) -> Result<(), String> {
+        self.send(&ClientMessage::Start)
+    }
+
+    pub fn send_direction(&mut self, tick: u64, direction: Direction) -> Result<(), String> {
+        self.send(&ClientMessage::Direction { tick, direction })
+    }
+
+    fn wait_room(&mut self) -> Result<usize, String> {
+        match self.recv()? {
+            ServerMessage::Room { room, .. } => Ok(room),
+            message => Err(unexpected(message)),
+        }
+    }
+}
+
+fn unexpected(message: ServerMessage) -> String {
+    match message {
+        ServerMessage::Error(e) => e,
+        message => format!("Unexpected message {}", message),
+    }
+}
@@ -0,0 +1,151 @@
+//! Multiplayer snake over TCP.
+//!
+//! Players connect to a [`Server`], create or join a room and start the
+//! game. Every tick the server waits for the direction of every player,
+//! moves all the snakes on the shared board and sends the new state to
+//! the whole room. See [`protocol`] for the messages.
+//!
+//! A single player game can be streamed to spectators with
+//! [`spectate::Broadcaster`].
+
+pub mod arena;
+pub mod client;
+pub mod protocol;
+mod server;
+pub mod spectate;
+
+pub use server::{Server, ServerConfig};
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::{SocketAddr, TcpStream},
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use snake::Direction;
+
+    use crate::{
+        client::Client,
+        protocol::{ServerMessage, State},
+        server::Clock,
+        Server, ServerConfig,
+    };
+
+    /// Only moves when told to
+    #[derive(Clone)]
+    struct ManualClock(Arc<Mutex<Instant>>);
+
+    impl ManualClock {
+        fn advance(&self, duration: Duration) {
+            *self.0.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn spawn_server(config: ServerConfig) -> SocketAddr {
+        spawn_server_with(Server::bind("127.0.0.1:0", config).unwrap())
+    }
+
+    fn spawn_server_with(server: Server) -> SocketAddr {
+        let addr = server.local_addr().unwrap();
+        server.spawn();
+        addr
+    }
+
+    fn recv_state(client: &mut Client) -> State {
+        match client.recv().unwrap() {
+            ServerMessage::State(state) => state,
+            message => panic!("Unexpected message {}", message),
+        }
+    }
+
+    #[test]
+    fn test_play() {
+        let addr = spawn_server(ServerConfig::default());
+
+        let mut alice = Client::connect(addr, "alice").unwrap();
+        let mut bob = Client::connect(addr, "bob").unwrap();
+        assert_ne!(alice.player(), bob.player());
+
+        let room = alice.create("snake2").unwrap();
+        bob.join(room).unwrap();
+        alice.start().unwrap();
+
+        let state = recv_state(&mut alice);
+        assert_eq!(state.tick, 0);
+        assert_eq!(state.snakes.len(), 2);
+        assert_eq!(recv_state(&mut bob), state);
+
+        for tick in 0..3 {
+            alice.send_direction(tick, Direction::Up).unwrap();
+            bob.send_direction(tick, Direction::Up).unwrap();
+
+            let state = recv_state(&mut alice);
+            assert_eq!(state.tick, tick + 1);
+            assert_eq!(recv_state(&mut bob), state);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let addr = spawn_server(ServerConfig::default());
+
+        let mut client = Client::connect(addr, "alice").unwrap();
+        assert_eq!(client.join(42).unwrap_err(), "Unknown room 42");
+        assert_eq!(client.create("foo").unwrap_err(), "Unknown level foo");
+
+        client.create("snake1").unwrap();
+        assert_eq!(client.create("snake1").unwrap_err(), "Already in a room");
+
+        // Old clients are refused
+        let mut stream = TcpStream::connect(addr).unwrap();
+        writeln!(stream, "HELLO 0 old").unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        assert_eq!(
+            line.trim_end().parse::<ServerMessage>().unwrap(),
+            ServerMessage::Error("Unsupported protocol version 0".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_timeout() {
+        let config = ServerConfig {
+            period: Duration::from_millis(10),
+            timeout: Duration::from_millis(200),
+        };
+        let clock = ManualClock(Arc::new(Mutex::new(Instant::now())));
+        let server = Server::bind("127.0.0.1:0", config.clone()).unwrap();
+        let addr = spawn_server_with(server.with_clock(clock.clone()));
+
+        let mut alice = Client::connect(addr, "alice").unwrap();
+        let mut bob = Client::connect(addr, "bob").unwrap();
+        let room = alice.create("snake2").unwrap();
+        bob.join(room).unwrap();
+        alice.start().unwrap();
+
+        // Bob doesn't play: every tick waits for the whole period, then he
+        // is dropped
+        let mut state = recv_state(&mut alice);
+        while state.snakes.len() == 2 {
+            // Zigzag so the snake never bites itself
+            let direction = [Direction::Up, Direction::Right][state.tick as usize % 2];
+            clock.advance(config.period);
+            alice.send_direction(state.tick, direction).unwrap();
+            state = recv_state(&mut alice);
+        }
+
+        assert_eq!(state.snakes[0].player, alice.player());
+        // Dropped on the first tick after the timeout
+        let periods = config.timeout.as_millis() / config.period.as_millis();
+        assert_eq!(state.tick, periods as u64 + 1);
+    }
+}
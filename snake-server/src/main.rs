@@ -0,0 +1,12 @@
+use snake_server::{Server, ServerConfig};
+
+fn main() {
+    let addr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:7878".to_owned());
+
+    let server = Server::bind(&addr, ServerConfig::default()).unwrap();
+    println!("Listening on {}", server.local_addr().unwrap());
+
+    server.run();
+}
@@ -0,0 +1,320 @@
+//! Messages are single lines of text: a keyword followed by its arguments,
+//! separated by spaces. Clients start with `HELLO <version> <name>`.
+
+use std::{fmt, str::FromStr};
+
+use snake::{Direction, Point};
+
+/// Bumped on every change of the messages
+pub const VERSION: u32 = 1;
+
+/// The board as seen by every player after a tick
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    pub tick: u64,
+    pub food: Point,
+    pub snakes: Vec<SnakeState>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnakeState {
+    pub player: usize,
+    pub alive: bool,
+    pub score: usize,
+    /// Head first. Empty once the snake is dead.
+    pub body: Vec<Point>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientMessage {
+    Hello {
+        version: u32,
+        name: String,
+    },
+    /// Create a room playing the campaign level with this name
+    Create {
+        level: String,
+    },
+    Join {
+        room: usize,
+    },
+    /// Start the game of the room
+    Start,
+    /// The direction of the player for the given tick
+    Direction {
+        tick: u64,
+        direction: Direction,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerMessage {
+    Welcome {
+        version: u32,
+        player: usize,
+    },
+    /// The player is in the room
+    Room {
+        room: usize,
+        level: String,
+    },
+    State(State),
+    /// Every snake of the room is dead
+    Over,
+    Error(String),
+}
+
+fn direction_to_char(direction: Direction) -> char {
+    match direction {
+        Direction::Up => 'U',
+        Direction::Down => 'D',
+        Direction::Left => 'L',
+        Direction::Right => 'R',
+    }
+}
+
+fn parse_direction(s: &str) -> Result<Direction, String> {
+    match s {
+        "U" => Ok(Direction::Up),
+        "D" => Ok(Direction::Down),
+        "L" => Ok(Direction::Left),
+        "R" => Ok(Direction::Right),
+        _ => Err(format!("Invalid direction {}", s)),
+    }
+}
+
+fn parse_number<N: FromStr>(s: &str) -> Result<N, String> {
+    s.parse().map_err(|_| format!("Invalid number {}", s))
+}
+
+fn parse_point(s: &str) -> Result<Point, String> {
+    let (x, y) = s
+        .split_once(',')
+        .ok_or_else(|| format!("Invalid point {}", s))?;
+    Ok(Point {
+        x: parse_number(x)?,
+        y: parse_number(y)?,
+    })
+}
+
+impl fmt::Display for ClientMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientMessage::Hello { version, name } => write!(f, "HELLO {} {}", version, name),
+            ClientMessage::Create { level } => write!(f, "CREATE {}", level),
+            ClientMessage::Join { room } => write!(f, "JOIN {}", room),
+            ClientMessage::Start => write!(f, "START"),
+            ClientMessage::Direction { tick, direction } => {
+                write!(f, "DIR {} {}", tick, direction_to_char(*direction))
+            }
+        }
+    }
+}
+
+impl FromStr for ClientMessage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let args: Vec<_> = s.split(' ').collect();
+        match args[..] {
+            ["HELLO", version, ref name @ ..] if !name.is_empty() => Ok(ClientMessage::Hello {
+                version: parse_number(version)?,
+                name: name.join(" "),
+            }),
+            ["CREATE", level] => Ok(ClientMessage::Create {
+                level: level.to_owned(),
+            }),
+            ["JOIN", room] => Ok(ClientMessage::Join {
+                room: parse_number(room)?,
+            }),
+            ["START"] => Ok(ClientMessage::Start),
+            ["DIR", tick, direction] => Ok(ClientMessage::Direction {
+                tick: parse_number(tick)?,
+                direction: parse_direction(direction)?,
+            }),
+            _ => Err(format!("Invalid message {}", s)),
+        }
+    }
+}
+
+/// `<player>:<score>:<alive>:<x>,<y>;<x>,<y>...` where `alive` is `1` or `0`
+impl fmt::Display for SnakeState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}:", self.player, self.score, self.alive as u8)?;
+        let body: Vec<_> = self
+            .body
+            .iter()
+            .map(|p| format!("{},{}", p.x, p.y))
+            .collect();
+        write!(f, "{}", body.join(";"))
+    }
+}
+
+impl FromStr for SnakeState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<_> = s.splitn(4, ':').collect();
+        let [player, score, alive, body] = fields[..] else {
+            return Err(format!("Invalid snake {}", s));
+        };
+
+        let body = if body.is_empty() {
+            vec![]
+        } else {
+            body.split(';').map(parse_point).collect::<Result<_, _>>()?
+        };
+
+        Ok(SnakeState {
+            player: parse_number(player)?,
+            score: parse_number(score)?,
+            alive: alive == "1",
+            body,
+        })
+    }
+}
+
+impl fmt::Display for ServerMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerMessage::Welcome { version, player } => {
+                write!(f, "WELCOME {} {}", version, player)
+            }
+            ServerMessage::Room { room, level } => write!(f, "ROOM {} {}", room, level),
+            ServerMessage::State(state) => {
+                write!(f, "STATE {} {},{}", state.tick, state.food.x, state.food.y)?;
+                for snake in &state.snakes {
+                    write!(f, " {}", snake)?;
+                }
+                Ok(())
+            }
+            ServerMessage::Over => write!(f, "OVER"),
+            ServerMessage::Error(message) => write!(f, "ERROR {}", message),
+        }
+    }
+}
+
+impl FromStr for ServerMessage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let args: Vec<_> = s.split(' ').collect();
+        match args[..] {
+            ["WELCOME", version, player] => Ok(ServerMessage::Welcome {
+                version: parse_number(version)?,
+                player: parse_number(player)?,
+            }),
+            ["ROOM", room, level] => Ok(ServerMessage::Room {
+                room: parse_number(room)?,
+                level: level.to_owned(),
+            }),
+            ["STATE", tick, food, ref snakes @ ..] => Ok(ServerMessage::State(State {
+                tick: parse_number(tick)?,
+                food: parse_point(food)?,
+                snakes: snakes.iter().map(|s| s.parse()).collect::<Result<_, _>>()?,
+            })),
+            ["OVER"] => Ok(ServerMessage::Over),
+            ["ERROR", ref message @ ..] => Ok(ServerMessage::Error(message.join(" "))),
+            _ => Err(format!("Invalid message {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use snake::{Direction, Point};
+
+    use super::{ClientMessage, ServerMessage, SnakeState, State, VERSION};
+
+    #[test]
+    fn test_client_message() {
+        let messages = [
+            (
+                ClientMessage::Hello {
+                    version: VERSION,
+                    name: "my name".to_owned(),
+                },
+                "HELLO 1 my name",
+            ),
+            (
+                ClientMessage::Create {
+                    level: "snake2".to_owned(),
+                },
+                "CREATE snake2",
+            ),
+            (ClientMessage::Join { room: 3 }, "JOIN 3"),
+            (ClientMessage::Start, "START"),
+            (
+                ClientMessage::Direction {
+                    tick: 12,
+                    direction: Direction::Left,
+                },
+                "DIR 12 L",
+            ),
+        ];
+
+        for (message, line) in messages {
+            assert_eq!(message.to_string(), line);
+            assert_eq!(line.parse::<ClientMessage>().unwrap(), message);
+        }
+
+        for invalid in ["", "HELLO 1", "JOIN x", "DIR 1 X", "FOO"] {
+            assert!(invalid.parse::<ClientMessage>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_server_message() {
+        let state = State {
+            tick: 4,
+            food: Point { x: 1, y: 2 },
+            snakes: vec![
+                SnakeState {
+                    player: 0,
+                    alive: true,
+                    score: 2,
+                    body: vec![Point { x: 3, y: 3 }, Point { x: 3, y: 2 }],
+                },
+                SnakeState {
+                    player: 5,
+                    alive: false,
+                    score: 0,
+                    body: vec![],
+                },
+            ],
+        };
+
+        let messages = [
+            (
+                ServerMessage::Welcome {
+                    version: VERSION,
+                    player: 7,
+                },
+                "WELCOME 1 7",
+            ),
+            (
+                ServerMessage::Room {
+                    room: 1,
+                    level: "snake1".to_owned(),
+                },
+                "ROOM 1 snake1",
+            ),
+            (
+                ServerMessage::State(state),
+                "STATE 4 1,2 0:2:1:3,3;3,2 5:0:0:",
+            ),
+            (ServerMessage::Over, "OVER"),
+            (
+                ServerMessage::Error("Unknown room 3".to_owned()),
+                "ERROR Unknown room 3",
+            ),
+        ];
+
+        for (message, line) in messages {
+            assert_eq!(message.to_string(), line);
+            assert_eq!(line.parse::<ServerMessage>().unwrap(), message);
+        }
+
+        assert!("STATE 1 1,2 0:1".parse::<ServerMessage>().is_err());
+    }
+}
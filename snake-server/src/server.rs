@@ -0,0 +1,491 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use snake::{campaign::Campaign, Direction};
+use tracing::{info, warn};
+
+use crate::{
+    arena::Arena,
+    protocol::{ClientMessage, ServerMessage, VERSION},
+};
+
+/// How the rooms are run
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// The longest time a tick waits for the directions of the players
+    pub period: Duration,
+    /// Players of a running game who don't send anything for this long
+    /// are dropped
+    pub timeout: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            period: Duration::from_millis(200),
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// The messages waiting to be written to a client. A client that lets
+/// this many pile up is disconnected.
+const SEND_QUEUE: usize = 64;
+
+/// Where the lobby reads the time, so that tests can move it by hand
+pub(crate) trait Clock: Send + 'static {
+    fn now(&self) -> Instant;
+}
+
+struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Hosts rooms where many snakes play on the same board.
+/// Every tick waits for the direction of every player, or for the period
+/// to elapse, then the new state is sent to the whole room.
+pub struct Server {
+    listener: TcpListener,
+    config: ServerConfig,
+    clock: Box<dyn Clock>,
+}
+
+impl Server {
+    pub fn bind(addr: impl ToSocketAddrs, config: ServerConfig) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            config,
+            clock: Box::new(SystemClock),
+        })
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_clock(self, clock: impl Clock) -> Self {
+        Self {
+            clock: Box::new(clock),
+            ..self
+        }
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Serve the clients forever
+    pub fn run(self) {
+        let (sender, receiver) = mpsc::channel();
+        let listener = self.listener;
+        thread::spawn(move || accept(listener, sender));
+
+        Lobby::new(self.config, self.clock).run(receiver);
+    }
+
+    pub fn spawn(self) -> JoinHandle<()> {
+        thread::spawn(move || self.run())
+    }
+}
+
+/// What the connections send to the lobby
+enum Event {
+    Connected(usize, TcpStream, SyncSender<String>),
+    Message(usize, Result<ClientMessage, String>),
+    Disconnected(usize),
+}
+
+fn accept(listener: TcpListener, sender: Sender<Event>) {
+    for (id, stream) in listener.incoming().enumerate() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Cannot accept a connection: {}", e);
+                continue;
+            }
+        };
+        let (reader, writer) = match (stream.try_clone(), stream.try_clone()) {
+            (Ok(reader), Ok(writer)) => (reader, writer),
+            (Err(e), _) | (_, Err(e)) => {
+                warn!("Cannot use the connection: {}", e);
+                continue;
+            }
+        };
+
+        let (lines, queue) = mpsc::sync_channel(SEND_QUEUE);
+        if sender.send(Event::Connected(id, stream, lines)).is_err() {
+            return;
+        }
+        let sender = sender.clone();
+        thread::spawn(move || read(id, reader, sender));
+        thread::spawn(move || write(writer, queue));
+    }
+}
+
+/// Write the lines sent by the lobby, so that a slow client never blocks
+/// it. The connection is closed once the lobby drops it.
+fn write(stream: TcpStream, queue: Receiver<String>) {
+    for line in queue {
+        // A broken connection is noticed by its reader
+        if writeln!(&stream, "{}", line).is_err() {
+            break;
+        }
+    }
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+fn read(id: usize, stream: TcpStream, sender: Sender<Event>) {
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if sender
+            .send(Event::Message(id, line.trim_end().parse()))
+            .is_err()
+        {
+            return;
+        }
+    }
+    let _ = sender.send(Event::Disconnected(id));
+}
+
+struct Connection {
+    /// Only to close the connection: the lines go through `lines`
+    stream: TcpStream,
+    lines: SyncSender<String>,
+    /// Set once the player said hello
+    name: Option<String>,
+    room: Option<usize>,
+    last_seen: Instant,
+}
+
+struct Room {
+    level: String,
+    arena: Arena,
+    players: Vec<usize>,
+    running: bool,
+    /// The directions received for the current tick
+    directions: BTreeMap<usize, Direction>,
+    /// When the current tick runs even if some directions are missing
+    deadline: Instant,
+}
+
+/// Owns every connection and every room
+struct Lobby {
+    config: ServerConfig,
+    clock: Box<dyn Clock>,
+    connections: HashMap<usize, Connection>,
+    rooms: HashMap<usize, Room>,
+    next_room: usize,
+}
+
+fn send(connections: &HashMap<usize, Connection>, id: usize, message: &ServerMessage) {
+    if let Some(connection) = connections.get(&id) {
+        match connection.lines.try_send(message.to_string()) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                warn!("Player {} is too slow, disconnecting", id);
+                // Its reader then reports it disconnected
+                let _ = connection.stream.shutdown(Shutdown::Both);
+            }
+            // The writer has stopped: the reader reports it
+            Err(TrySendError::Disconnected(_)) => {}
+        }
+    }
+}
+
+fn broadcast(connections: &HashMap<usize, Connection>, room: &Room, message: &ServerMessage) {
+    for player in &room.players {
+        send(connections, *player, message);
+    }
+}
+
+impl Lobby {
+    fn new(config: ServerConfig, clock: Box<dyn Clock>) -> Self {
+        Self {
+            config,
+            clock,
+            connections: HashMap::new(),
+            rooms: HashMap::new(),
+            next_room: 0,
+        }
+    }
+
+    fn run(mut self, receiver: Receiver<Event>) {
+        loop {
+            let timeout = self
+                .rooms
+                .values()
+                .filter(|r| r.running)
+                .map(|r| r.deadline.saturating_duration_since(self.clock.now()))
+                .min()
+                .unwrap_or(self.config.period);
+
+            match receiver.recv_timeout(timeout) {
+                Ok(event) => self.handle(event),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+
+            self.advance_rooms();
+        }
+    }
+
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::Connected(id, stream, lines) => {
+                info!("Player {} connected", id);
+                self.connections.insert(
+                    id,
+                    Connection {
+                        stream,
+                        lines,
+                        name: None,
+                        room: None,
+                        last_seen: self.clock.now(),
+                    },
+                );
+            }
+            Event::Disconnected(id) => {
+                info!("Player {} disconnected", id);
+                self.leave(id);
+                self.connections.remove(&id);
+            }
+            Event::Message(id, Err(e)) => {
+                send(&self.connections, id, &ServerMessage::Error(e));
+            }
+            Event::Message(id, Ok(message)) => {
+                if let Err(e) = self.handle_message(id, message) {
+                    send(&self.connections, id, &ServerMessage::Error(e));
+                }
+            }
+        }
+    }
+
+    fn handle_message(&mut self, id: usize, message: ClientMessage) -> Result<(), String> {
+        let connection = match self.connections.get_mut(&id) {
+            Some(connection) => connection,
+            None => return Ok(()),
+        };
+        connection.last_seen = self.clock.now();
+
+        if connection.name.is_none() && !matches!(message, ClientMessage::Hello { .. }) {
+            return Err("Say HELLO first".to_owned());
+        }
+
+        match message {
+            ClientMessage::Hello { version, name } => {
+                self.hello(id, version, name);
+                Ok(())
+            }
+            ClientMessage::Create { level } => {
+                if self.connections[&id].room.is_some() {
+                    return Err("Already in a room".to_owned());
+                }
+                let board = Campaign::default()
+                    .levels()
+                    .iter()
+                    .find(|l| l.name == level)
+                    .map(|l| l.board)
+                    .ok_or_else(|| format!("Unknown level {}", level))?;
+
+                let room = self.next_room;
+                self.next_room += 1;
+                self.rooms.insert(
+                    room,
+                    Room {
+                        level,
+                        arena: Arena::new(board, fastrand::u64(..))?,
+                        players: vec![],
+                        running: false,
+                        directions: BTreeMap::new(),
+                        deadline: self.clock.now(),
+                    },
+                );
+
+                self.join(id, room)
+            }
+            ClientMessage::Join { room } => self.join(id, room),
+            ClientMessage::Start => {
+                let room_id = self.connections[&id]
+                    .room
+                    .ok_or_else(|| "Not in a room".to_owned())?;
+                let room = self.rooms.get_mut(&room_id).unwrap();
+                if room.running {
+                    return Err("Already started".to_owned());
+                }
+
+                info!("Room {} started", room_id);
+                let now = self.clock.now();
+                room.running = true;
+                room.deadline = now + self.config.period;
+                for player in &room.players {
+                    if let Some(connection) = self.connections.get_mut(player) {
+                        connection.last_seen = now;
+                    }
+                }
+                broadcast(
+                    &self.connections,
+                    room,
+                    &ServerMessage::State(room.arena.state()),
+                );
+                Ok(())
+            }
+            ClientMessage::Direction { tick, direction } => {
+                let room = self.connections[&id]
+                    .room
+                    .and_then(|room| self.rooms.get_mut(&room));
+                // Directions for old ticks are ignored
+                if let Some(room) = room {
+                    if room.running && room.arena.tick_count() == tick {
+                        room.directions.insert(id, direction);
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn hello(&mut self, id: usize, version: u32, name: String) {
+        if version != VERSION {
+            send(
+                &self.connections,
+                id,
+                &ServerMessage::Error(format!("Unsupported protocol version {}", version)),
+            );
+            // Its writer closes the connection once the error is written
+            self.leave(id);
+            self.connections.remove(&id);
+            return;
+        }
+
+        info!("Player {} is {}", id, name);
+        self.connections.get_mut(&id).unwrap().name = Some(name);
+        send(
+            &self.connections,
+            id,
+            &ServerMessage::Welcome {
+                version: VERSION,
+                player: id,
+            },
+        );
+    }
+
+    fn join(&mut self, id: usize, room_id: usize) -> Result<(), String> {
+        if self.connections[&id].room.is_some() {
+            return Err("Already in a room".to_owned());
+        }
+        let room = self
+            .rooms
+            .get_mut(&room_id)
+            .ok_or_else(|| format!("Unknown room {}", room_id))?;
+        if room.running {
+            return Err(format!("Room {} already started", room_id));
+        }
+
+        room.arena.add_player(id)?;
+        room.players.push(id);
+        self.connections.get_mut(&id).unwrap().room = Some(room_id);
+
+        send(
+            &self.connections,
+            id,
+            &ServerMessage::Room {
+                room: room_id,
+                level: room.level.clone(),
+            },
+        );
+        Ok(())
+    }
+
+    fn leave(&mut self, id: usize) {
+        let room_id = match self.connections.get_mut(&id).and_then(|c| c.room.take()) {
+            Some(room_id) => room_id,
+            None => return,
+        };
+
+        if let Some(room) = self.rooms.get_mut(&room_id) {
+            room.players.retain(|p| *p != id);
+            room.arena.remove_player(id);
+            room.directions.remove(&id);
+
+            if room.players.is_empty() {
+                self.rooms.remove(&room_id);
+            }
+        }
+    }
+
+    /// Run the ticks of the rooms that have every direction or whose
+    /// deadline has passed
+    fn advance_rooms(&mut self) {
+        let now = self.clock.now();
+
+        // Drop the players who have gone silent
+        let silent: Vec<usize> = self
+            .connections
+            .iter()
+            .filter(|(_, c)| {
+                let running = c
+                    .room
+                    .and_then(|room| self.rooms.get(&room))
+                    .is_some_and(|room| room.running);
+                running && now.duration_since(c.last_seen) > self.config.timeout
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for id in silent {
+            info!("Player {} timed out", id);
+            send(
+                &self.connections,
+                id,
+                &ServerMessage::Error("Timed out".to_owned()),
+            );
+            self.leave(id);
+        }
+
+        let mut over = vec![];
+        for (room_id, room) in self.rooms.iter_mut().filter(|(_, r)| r.running) {
+            let state = room.arena.state();
+            let ready = state
+                .snakes
+                .iter()
+                .filter(|s| s.alive)
+                .all(|s| room.directions.contains_key(&s.player));
+            if !ready && now < room.deadline {
+                continue;
+            }
+
+            room.arena.tick(&room.directions);
+            room.directions.clear();
+            room.deadline = now + self.config.period;
+            broadcast(
+                &self.connections,
+                room,
+                &ServerMessage::State(room.arena.state()),
+            );
+
+            if room.arena.is_over() {
+                broadcast(&self.connections, room, &ServerMessage::Over);
+                over.push(*room_id);
+            }
+        }
+
+        for room_id in over {
+            info!("Room {} is over", room_id);
+            let room = self.rooms.remove(&room_id).unwrap();
+            for player in room.players {
+                if let Some(connection) = self.connections.get_mut(&player) {
+                    connection.room = None;
+                }
+            }
+        }
+    }
+}
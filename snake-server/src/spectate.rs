@@ -0,0 +1,182 @@
+use std::{
+    io,
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use snake::{
+    stream::{Encoder, Frame},
+    topology::Topology,
+    Game, Snapshot,
+};
+use tracing::{info, warn};
+use tungstenite::{Message, WebSocket};
+
+struct Shared {
+    encoder: Encoder,
+    spectators: Vec<WebSocket<TcpStream>>,
+}
+
+/// Streams a game to read-only spectators over WebSocket.
+/// Every message is a [`Frame`].
+pub struct Broadcaster {
+    shared: Arc<Mutex<Shared>>,
+    addr: SocketAddr,
+}
+
+impl Broadcaster {
+    /// Listen for spectators of `game`
+    pub fn bind<T: Topology>(addr: impl ToSocketAddrs, game: &Game<T>) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let shared = Arc::new(Mutex::new(Shared {
+            encoder: Encoder::new(game),
+            spectators: vec![],
+        }));
+
+        let accepting = shared.clone();
+        thread::spawn(move || accept(listener, accepting));
+
+        Ok(Self { shared, addr })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn spectators(&self) -> usize {
+        self.shared.lock().unwrap().spectators.len()
+    }
+
+    /// Stream another game, like a new level: the spectators get its board
+    /// and its keyframe
+    pub fn restart<T: Topology>(&self, game: &Game<T>) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.encoder = Encoder::new(game);
+
+        let frames = shared.encoder.catch_up();
+        shared
+            .spectators
+            .retain_mut(|ws| frames.iter().all(|f| send(ws, f)));
+    }
+
+    /// Send the snapshot of the last tick to every spectator
+    pub fn push(&self, snapshot: Snapshot) {
+        let mut shared = self.shared.lock().unwrap();
+        let frame = shared.encoder.push(snapshot);
+
+        // Spectators who went away are dropped
+        shared.spectators.retain_mut(|ws| send(ws, &frame));
+    }
+}
+
+fn send(ws: &mut WebSocket<TcpStream>, frame: &Frame) -> bool {
+    ws.send(Message::Text(frame.to_string())).is_ok()
+}
+
+fn accept(listener: TcpListener, shared: Arc<Mutex<Shared>>) {
+    for stream in listener.incoming() {
+        let ws = stream
+            .map_err(|e| e.to_string())
+            .and_then(|stream| tungstenite::accept(stream).map_err(|e| e.to_string()));
+        let mut ws = match ws {
+            Ok(ws) => ws,
+            Err(e) => {
+                warn!("Cannot accept a spectator: {}", e);
+                continue;
+            }
+        };
+
+        // Under the lock: no frame can be pushed between the catch up and
+        // the registration
+        let mut shared = shared.lock().unwrap();
+        if shared.encoder.catch_up().iter().all(|f| send(&mut ws, f)) {
+            info!("New spectator");
+            shared.spectators.push(ws);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use snake::{
+        stream::{Frame, View},
+        Direction, Game, SNAKE_1, SNAKE_2,
+    };
+    use tungstenite::Message;
+
+    use super::Broadcaster;
+
+    fn connect(broadcaster: &Broadcaster) -> impl FnMut() -> Frame {
+        let url = format!("ws://{}", broadcaster.local_addr());
+        let (mut ws, _) = tungstenite::connect(url).unwrap();
+        move || match ws.read().unwrap() {
+            Message::Text(text) => text.parse().unwrap(),
+            message => panic!("Unexpected message {}", message),
+        }
+    }
+
+    fn wait_spectators(broadcaster: &Broadcaster, n: usize) {
+        while broadcaster.spectators() < n {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_spectate() {
+        let mut game: Game = SNAKE_2.parse().unwrap();
+        let broadcaster = Broadcaster::bind("127.0.0.1:0", &game).unwrap();
+
+        let mut first = connect(&broadcaster);
+        wait_spectators(&broadcaster, 1);
+
+        for _ in 0..5 {
+            game.tick(Direction::Right);
+            broadcaster.push(game.last_snapshot());
+        }
+
+        let mut late = connect(&broadcaster);
+        wait_spectators(&broadcaster, 2);
+
+        game.tick(Direction::Up);
+        broadcaster.push(game.last_snapshot());
+
+        let mut view = View::default();
+        for _ in 0..8 {
+            view.apply(first()).unwrap();
+        }
+
+        // Board, keyframe of the 5th tick and the last delta
+        let mut late_view = View::default();
+        let board = late();
+        assert!(matches!(board, Frame::Board { .. }));
+        late_view.apply(board).unwrap();
+        let key = late();
+        assert!(matches!(key, Frame::Key { tick: 5, .. }));
+        late_view.apply(key).unwrap();
+        late_view.apply(late()).unwrap();
+
+        let expected = game.last_snapshot();
+        for view in [&view, &late_view] {
+            assert_eq!(view.tick(), 6);
+            assert_eq!(view.dim(), game.dim());
+            assert_eq!(view.snapshot().unwrap().snake[0], expected.snake[0]);
+            assert_eq!(
+                view.snapshot().unwrap().food_position,
+                expected.food_position
+            );
+        }
+
+        // Next level
+        let game: Game = SNAKE_1.parse().unwrap();
+        broadcaster.restart(&game);
+        for _ in 0..2 {
+            view.apply(first()).unwrap();
+        }
+        assert_eq!(view.tick(), 0);
+        assert_eq!(view.walls(), &game.walls()[..]);
+    }
+}
@@ -0,0 +1,226 @@
+use alloc::{vec, vec::Vec};
+use core::time::Duration;
+
+use crate::{Snapshot, SNAKE_1, SNAKE_2, SNAKE_3, SNAKE_4};
+
+/// What the player has to do to complete a level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    /// Reach at least this score
+    Score(usize),
+    /// Grow the snake (head included) to at least this length
+    Length(usize),
+    /// Stay alive for at least this game time
+    Time(Duration),
+}
+
+impl Goal {
+    pub fn is_reached(&self, snapshot: &Snapshot) -> bool {
+        match *self {
+            Goal::Score(score) => snapshot.score >= score,
+            Goal::Length(length) => snapshot.snake.len() >= length,
+            Goal::Time(time) => snapshot.elapsed >= time,
+        }
+    }
+}
+
+/// When a level becomes playable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnlockRule {
+    /// Always playable
+    Always,
+    /// Playable once the previous level of the campaign is completed
+    Previous,
+    /// Playable once the level with this name is completed
+    Completed(&'static str),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Level {
+    pub name: &'static str,
+    pub board: &'static str,
+    pub goal: Goal,
+    pub unlock: UnlockRule,
+}
+
+/// An ordered list of levels and the progress made on them
+pub struct Campaign {
+    levels: Vec<Level>,
+    completed: Vec<bool>,
+}
+
+impl Campaign {
+    pub fn new(levels: Vec<Level>) -> Self {
+        let completed = vec![false; levels.len()];
+        Self { levels, completed }
+    }
+
+    pub fn levels(&self) -> &[Level] {
+        &self.levels
+    }
+
+    pub fn level(&self, index: usize) -> Option<&Level> {
+        self.levels.get(index)
+    }
+
+    pub fn is_completed(&self, index: usize) -> bool {
+        self.completed.get(index).copied().unwrap_or(false)
+    }
+
+    pub fn is_unlocked(&self, index: usize) -> bool {
+        let level = match self.levels.get(index) {
+            Some(level) => level,
+            None => return false,
+        };
+
+        match level.unlock {
+            UnlockRule::Always => true,
+            UnlockRule::Previous => index == 0 || self.is_completed(index - 1),
+            UnlockRule::Completed(name) => self
+                .levels
+                .iter()
+                .position(|l| l.name == name)
+                .is_some_and(|i| self.is_completed(i)),
+        }
+    }
+
+    /// Mark the level as completed if the snapshot reaches its goal.
+    /// Returns `true` only the first time the goal is reached.
+    pub fn record(&mut self, index: usize, snapshot: &Snapshot) -> bool {
+        let level = match self.levels.get(index) {
+            Some(level) => level,
+            None => return false,
+        };
+
+        if self.completed[index] || !level.goal.is_reached(snapshot) {
+            return false;
+        }
+
+        self.completed[index] = true;
+        true
+    }
+
+    /// The first unlocked level after `index`, if any
+    pub fn next_level(&self, index: usize) -> Option<usize> {
+        (index + 1..self.levels.len()).find(|i| self.is_unlocked(*i))
+    }
+}
+
+impl Default for Campaign {
+    fn default() -> Self {
+        Self::new(vec![
+            Level {
+                name: "snake1",
+                board: SNAKE_1,
+                goal: Goal::Score(5),
+                unlock: UnlockRule::Always,
+            },
+            Level {
+                name: "snake2",
+                board: SNAKE_2,
+                goal: Goal::Length(10),
+                unlock: UnlockRule::Previous,
+            },
+            Level {
+                name: "snake3",
+                board: SNAKE_3,
+                goal: Goal::Time(Duration::from_secs(60)),
+                unlock: UnlockRule::Previous,
+            },
+            Level {
+                name: "snake4",
+                board: SNAKE_4,
+                goal: Goal::Score(10),
+                unlock: UnlockRule::Previous,
+            },
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{Direction, Game, Point};
+
+    use super::{Campaign, Goal, Level, UnlockRule};
+
+    static LEVEL: &str = "\
+##########
+#        #
+#   f    #
+#   h    #
+#   b    #
+##########";
+
+    fn campaign() -> Campaign {
+        Campaign::new(vec![
+            Level {
+                name: "first",
+                board: LEVEL,
+                goal: Goal::Score(1),
+                unlock: UnlockRule::Always,
+            },
+            Level {
+                name: "second",
+                board: LEVEL,
+                goal: Goal::Length(3),
+                unlock: UnlockRule::Previous,
+            },
+            Level {
+                name: "third",
+                board: LEVEL,
+                goal: Goal::Time(Duration::from_secs(2)),
+                unlock: UnlockRule::Completed("first"),
+            },
+        ])
+    }
+
+    #[test]
+    fn test_unlock() {
+        let mut campaign = campaign();
+
+        assert!(campaign.is_unlocked(0));
+        assert!(!campaign.is_unlocked(1));
+        assert!(!campaign.is_unlocked(2));
+        assert!(!campaign.is_unlocked(3));
+        assert_eq!(campaign.next_level(0), None);
+
+        let mut game: Game = LEVEL.parse().unwrap();
+        game.tick(Direction::Up);
+        let snapshot = game.last_snapshot();
+
+        assert!(campaign.record(0, &snapshot));
+        // Already completed
+        assert!(!campaign.record(0, &snapshot));
+
+        assert!(campaign.is_completed(0));
+        assert!(campaign.is_unlocked(1));
+        assert!(campaign.is_unlocked(2));
+        assert_eq!(campaign.next_level(0), Some(1));
+    }
+
+    #[test]
+    fn test_goals() {
+        let mut game: Game = LEVEL.parse().unwrap();
+
+        let snapshot = game.last_snapshot();
+        assert!(!Goal::Score(1).is_reached(&snapshot));
+        assert!(Goal::Length(2).is_reached(&snapshot));
+        assert!(!Goal::Length(3).is_reached(&snapshot));
+        assert!(!Goal::Time(Duration::from_secs(2)).is_reached(&snapshot));
+
+        // Eat the food
+        game.tick(Direction::Up);
+        let snapshot = game.last_snapshot();
+        assert!(Goal::Score(1).is_reached(&snapshot));
+        assert!(!Goal::Time(Duration::from_secs(2)).is_reached(&snapshot));
+
+        // Grow
+        game.tick(Direction::Right);
+        let snapshot = game.last_snapshot();
+        assert_eq!(snapshot.snake[0], Point { x: 5, y: 3 });
+        assert!(Goal::Length(3).is_reached(&snapshot));
+        assert!(Goal::Time(Duration::from_secs(2)).is_reached(&snapshot));
+    }
+}
@@ -1,8 +1,36 @@
-use std::{str::FromStr, time::Duration};
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
-use tracing::info;
+extern crate alloc;
 
-#[derive(Debug, Clone, Copy)]
+use alloc::{borrow::ToOwned, boxed::Box, format, string::String, vec, vec::Vec};
+use core::{fmt, str::FromStr, time::Duration};
+
+use rng::Rng;
+
+/// Log with `tracing`, when there is `std`
+macro_rules! info {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "std")]
+        tracing::info!($($arg)*);
+    };
+}
+
+pub mod campaign;
+pub mod observer;
+pub mod replay;
+mod rng;
+pub mod scores;
+pub mod share;
+pub mod stream;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod topology;
+
+use observer::GameObserver;
+use replay::Replay;
+use topology::{Topology, Torus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Up,
     Down,
@@ -35,20 +63,15 @@ struct Snake {
 }
 
 impl Snake {
-    pub fn r#move(
-        &mut self,
-        direction: Direction,
-        should_add_new_body_piece: bool,
-        w: usize,
-        h: usize,
-    ) -> Point {
+    pub fn r#move(&mut self, new_head: Point, should_add_new_body_piece: bool) -> Point {
         info!("move");
         self.move_body(should_add_new_body_piece);
-        self.move_head(direction, w, h);
+        self.head = new_head;
 
         self.head
     }
 
+    #[cfg(any(test, feature = "testing"))]
     pub fn contains(&self, point: Point) -> bool {
         self.head == point || self.body.contains(&point)
     }
@@ -57,9 +80,21 @@ impl Snake {
         self.body.contains(&point)
     }
 
+    /// From the head to the tail
+    fn cells(&self) -> Vec<Point> {
+        let len = self.body.len();
+        let mut cells = vec![self.head];
+        cells.extend((0..len).map(|i| self.body[(self.index + len - i) % len]));
+        cells
+    }
+
     fn move_body(&mut self, should_add_new_body_piece: bool) {
+        // `body` is a ring: the piece after `index` is the tail
         if should_add_new_body_piece {
-            self.body.push(self.body[self.index]);
+            // The tail stays, the old head becomes a new piece
+            self.index += 1;
+            self.body.insert(self.index, self.head);
+            return;
         }
 
         let index_to_move = (self.index + 1) % self.body.len();
@@ -68,38 +103,19 @@ impl Snake {
 
         self.index = index_to_move;
     }
-
-    fn move_head(&mut self, direction: Direction, w: usize, h: usize) {
-        match direction {
-            Direction::Up => self.head.y = (self.head.y + 1) % h,
-            Direction::Down => {
-                self.head.y = if self.head.y == 0 {
-                    h - 1
-                } else {
-                    self.head.y - 1
-                }
-            }
-            Direction::Right => self.head.x = (self.head.x + 1) % w,
-            Direction::Left => {
-                self.head.x = if self.head.x == 0 {
-                    w - 1
-                } else {
-                    self.head.x - 1
-                }
-            }
-        };
-    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Snapshot {
     pub on_food: bool,
     pub on_wall: bool,
     pub eat_itself: bool,
     pub food_position: Point,
+    /// From the head to the tail
     pub snake: Vec<Point>,
     pub score: usize,
     pub period_duration: Duration,
+    pub elapsed: Duration,
 }
 
 impl Snapshot {
@@ -115,7 +131,32 @@ impl Snapshot {
     }
 }
 
-pub struct Game {
+/// A tick run by [`Game::advance`]
+#[derive(Debug, Clone)]
+pub enum TickOutcome {
+    /// The snake moved and it is still alive
+    Moved(Snapshot),
+    /// The snake died: no more ticks will run
+    GameOver(Snapshot),
+}
+
+/// Most ticks run by one [`Game::advance`]: all of them take the same direction
+pub const MAX_ADVANCE_TICKS: usize = 4;
+
+/// What lies under a cell of the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Terrain {
+    Free,
+    Wall,
+    /// Not part of the board
+    Void,
+}
+
+/// A game on a board whose cells are connected as `T` says.
+/// By default the edges of the board wrap around.
+pub struct Game<T: Topology = Torus> {
+    /// How the cells are connected
+    topology: T,
     /// Dim of board
     width: usize,
     height: usize,
@@ -123,10 +164,19 @@ pub struct Game {
     snake: Snake,
     /// List of walls
     walls: Vec<Point>,
+    /// The terrain of every cell, row by row from the bottom
+    terrain: Vec<Terrain>,
+    /// Length of every row as written in the level, bottom row first.
+    /// Used to print the board back without the padding voids.
+    row_lengths: Vec<usize>,
     /// where's the food?
     food: Point,
     /// The direction taken previously
-    previous_direction: Direction,
+    previous_direction: T::Direction,
+    /// The direction used by the next tick run by `advance`
+    next_direction: T::Direction,
+    /// Time not spent by `advance` yet
+    accumulator: Duration,
     /// Number of new piece of body we need to generate
     new_piece_to_generate: usize,
 
@@ -134,45 +184,65 @@ pub struct Game {
     score: usize,
     /// The period duration
     period_duration: Duration,
+    /// Game time spent so far: the sum of the periods of all ticks
+    elapsed: Duration,
+
+    /// Seed used to place the food
+    seed: u64,
+    rng: Rng,
+    /// Directions taken so far, used to replay the game
+    directions: Vec<T::Direction>,
+
+    /// Notified on every tick
+    observers: Vec<Box<dyn GameObserver>>,
 
     /// Track snapshot of last tick
     last_snapshot: Snapshot,
 }
 
-impl Game {
-    pub fn tick(&mut self, mut direction: Direction) {
+impl<T: Topology> Game<T> {
+    pub fn tick(&mut self, mut direction: T::Direction) {
         info!("tick");
-        if !direction.is_compatible_with(self.previous_direction) {
+        if T::is_opposite(direction, self.previous_direction) {
             direction = self.previous_direction;
         }
         self.previous_direction = direction;
+        self.directions.push(direction);
+
+        let new_head = self.next_cell(self.snake.head, self.previous_direction);
 
-        let should_add_new_body_piece = self.new_piece_to_generate > 0;
+        // A snake that cannot move cannot grow either
+        let should_add_new_body_piece = self.new_piece_to_generate > 0 && new_head.is_some();
         if should_add_new_body_piece {
             self.new_piece_to_generate -= 1;
         }
 
-        let head = self.snake.r#move(
-            self.previous_direction,
-            should_add_new_body_piece,
-            self.width,
-            self.height,
-        );
-
-        let on_wall = self.walls.contains(&head);
+        // Leaving the board is like hitting a wall
+        let (head, on_wall) = match new_head {
+            Some(new_head) => {
+                let head = self.snake.r#move(new_head, should_add_new_body_piece);
+                (head, self.terrain(head) == Terrain::Wall)
+            }
+            None => (self.snake.head, true),
+        };
         let on_food = head == self.food;
 
         let eat_itself = self.snake.on_body(head);
 
+        self.elapsed += self.period_duration;
+
+        let previous_period_duration = self.period_duration;
         if on_food {
             self.new_piece_to_generate += 1;
-            self.food = self.generate_new_food_position();
+            // A full board keeps the food where it is
+            if let Some(food) = self.generate_new_food_position() {
+                self.food = food;
+            }
             self.score += 1;
             self.period_duration = calculate_period_duration(self.score);
         }
 
-        let mut snake = self.snake.body.clone();
-        snake.insert(0, self.snake.head);
+        let snake = self.snake.cells();
 
         self.last_snapshot = Snapshot {
             on_food,
@@ -182,7 +252,75 @@ impl Game {
             snake,
             score: self.score,
             period_duration: self.period_duration,
+            elapsed: self.elapsed,
         };
+
+        let length = self.last_snapshot.snake.len();
+        let reason = self.last_snapshot.get_game_over_reason();
+        for observer in &mut self.observers {
+            if should_add_new_body_piece {
+                observer.on_grow(length);
+            }
+            if on_food {
+                observer.on_food_eaten(head);
+                observer.on_food_spawned(self.food);
+            }
+            if previous_period_duration != self.period_duration {
+                observer.on_speed_change(self.period_duration);
+            }
+            if let Some(reason) = reason {
+                observer.on_death(reason);
+            }
+        }
+    }
+
+    pub fn add_observer(&mut self, observer: impl GameObserver + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// The direction of the last tick: the next one cannot take the
+    /// opposite one
+    pub fn direction(&self) -> T::Direction {
+        self.previous_direction
+    }
+
+    /// Set the direction of the ticks run by [`Game::advance`]
+    pub fn set_direction(&mut self, direction: T::Direction) {
+        self.next_direction = direction;
+    }
+
+    /// Let `elapsed` time pass and run as many ticks as the current speed
+    /// allows, at most [`MAX_ADVANCE_TICKS`]. The time left is kept for the
+    /// next call, unless the cap was hit: a long pause is not caught up.
+    pub fn advance(&mut self, elapsed: Duration) -> Vec<TickOutcome> {
+        let mut outcomes = vec![];
+        if self.is_over() {
+            return outcomes;
+        }
+
+        self.accumulator += elapsed;
+        while self.accumulator >= self.period_duration {
+            if outcomes.len() == MAX_ADVANCE_TICKS {
+                self.accumulator = Duration::ZERO;
+                break;
+            }
+            self.accumulator -= self.period_duration;
+            self.tick(self.next_direction);
+
+            let snapshot = self.last_snapshot();
+            if self.is_over() {
+                self.accumulator = Duration::ZERO;
+                outcomes.push(TickOutcome::GameOver(snapshot));
+                break;
+            }
+            outcomes.push(TickOutcome::Moved(snapshot));
+        }
+
+        outcomes
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.last_snapshot.get_game_over_reason().is_some()
     }
 
     pub fn last_snapshot(&self) -> Snapshot {
@@ -197,93 +335,259 @@ impl Game {
         self.walls.clone()
     }
 
-    fn generate_new_food_position(&self) -> Point {
-        info!("generate_new_food_position");
-        let x = fastrand::usize(..(self.width as usize));
-        let y = fastrand::usize(..(self.height as usize));
-        let p = Point { x, y };
+    pub fn voids(&self) -> Vec<Point> {
+        self.points_with(Terrain::Void).collect()
+    }
 
-        if self.walls.contains(&p) {
-            return self.generate_new_food_position();
+    /// Restart the food placement from `seed`.
+    /// Call it before the first tick to make the game reproducible.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = Rng::with_seed(seed);
+    }
+
+    /// The directions taken so far
+    pub fn directions(&self) -> &[T::Direction] {
+        &self.directions
+    }
+
+    fn terrain(&self, point: Point) -> Terrain {
+        self.terrain[point.y * self.width + point.x]
+    }
+
+    /// The cells of `terrain`, row by row from the bottom
+    fn points_with(&self, terrain: Terrain) -> impl Iterator<Item = Point> + '_ {
+        let width = self.width;
+        self.terrain
+            .iter()
+            .enumerate()
+            .filter(move |(_, t)| **t == terrain)
+            .map(move |(i, _)| Point {
+                x: i % width,
+                y: i / width,
+            })
+    }
+
+    /// The cell reached moving from `point` towards `direction`.
+    /// Void cells are skipped, as if the board wrapped around them.
+    fn next_cell(&self, mut point: Point, direction: T::Direction) -> Option<Point> {
+        for _ in 0..self.width * self.height {
+            point = self
+                .topology
+                .neighbour(point, direction, self.width, self.height)?;
+            if self.terrain(point) != Terrain::Void {
+                return Some(point);
+            }
+        }
+
+        // Only void in that direction
+        None
+    }
+
+    /// Put the body read row by row in the order of the ring, from the tail
+    /// to the neck, walking from the head. The cells the walk does not reach
+    /// go at the tail.
+    fn order_body(&mut self) {
+        let mut rest = core::mem::take(&mut self.snake.body);
+        let mut body = Vec::with_capacity(rest.len());
+        let mut last = self.snake.head;
+        while let Some(i) = rest.iter().position(|p| {
+            T::DIRECTIONS
+                .iter()
+                .any(|d| self.next_cell(last, *d) == Some(*p))
+        }) {
+            last = rest.remove(i);
+            body.push(last);
         }
+        body.extend(rest);
+        body.reverse();
 
-        if self.snake.contains(p) {
-            return self.generate_new_food_position();
+        self.snake.index = body.len() - 1;
+        self.snake.body = body;
+        self.last_snapshot.snake = self.snake.cells();
+    }
+
+    /// The cells where the food can be placed: no wall, no void, no snake
+    fn free_cells(&self) -> Vec<Point> {
+        let mut on_snake = vec![false; self.terrain.len()];
+        for p in core::iter::once(&self.snake.head).chain(&self.snake.body) {
+            on_snake[p.y * self.width + p.x] = true;
+        }
+
+        self.points_with(Terrain::Free)
+            .filter(|p| !on_snake[p.y * self.width + p.x])
+            .collect()
+    }
+
+    /// A free cell picked at random: `None` when the board is full
+    fn generate_new_food_position(&mut self) -> Option<Point> {
+        info!("generate_new_food_position");
+        let free = self.free_cells();
+        if free.is_empty() {
+            return None;
         }
 
-        p
+        Some(free[self.rng.usize(free.len())])
     }
 }
 
-impl FromStr for Game {
-    type Err = String;
+impl<T: Topology<Direction = Direction>> Game<T> {
+    /// The seed and the directions taken so far
+    pub fn replay(&self) -> Replay {
+        Replay {
+            seed: self.seed,
+            directions: self.directions.clone(),
+        }
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl<T: Topology + Default> Game<T> {
+    /// Reads a level like `FromStr` does, placing the food from `seed`.
+    /// Without `std` it is the way to get a game that is not seeded with 0.
+    pub fn from_str_seeded(s: &str, seed: u64) -> Result<Self, String> {
         let h = s.lines().count();
-        let w = s.lines().next().unwrap().len();
-
-        let chars = s.lines().rev().flat_map(|l| l.chars());
+        // Rows can have different lengths: the board is as wide as the longest one
+        let w = s.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+        T::check_size(w, h)?;
 
         let mut snake_head = None;
         let mut snake_body = vec![];
         let mut food = None;
-        let mut walls = Vec::with_capacity(h as usize * w as usize);
-        for (i, c) in chars.enumerate() {
-            let point = index_to_coordinate(i, w);
-            match c {
-                '#' => {
-                    walls.push(point);
-                }
-                ' ' => {}
-                'h' => {
-                    snake_head = Some(point);
+        let mut walls = Vec::with_capacity(h * w);
+        let mut terrain = vec![Terrain::Free; h * w];
+        let mut row_lengths = Vec::with_capacity(h);
+        for (y, line) in s.lines().rev().enumerate() {
+            let mut row_length = 0;
+            for (x, c) in line.chars().enumerate() {
+                let point = Point { x, y };
+                match c {
+                    '#' => {
+                        walls.push(point);
+                        terrain[y * w + x] = Terrain::Wall;
+                    }
+                    ' ' => {}
+                    '.' => {
+                        terrain[y * w + x] = Terrain::Void;
+                    }
+                    'h' => {
+                        if snake_head.is_some() {
+                            return Err(format!("Second snake head at {},{}", x, y));
+                        }
+                        snake_head = Some(point);
+                    }
+                    'b' => {
+                        snake_body.push(point);
+                    }
+                    'f' => {
+                        if food.is_some() {
+                            return Err(format!("Second food at {},{}", x, y));
+                        }
+                        food = Some(point);
+                    }
+                    _ => return Err(format!("Invalid char {} at {},{}", c, x, y)),
                 }
-                'b' => {
-                    snake_body.push(point);
-                }
-                'f' => {
-                    food = Some(point);
-                }
-                _ => return Err(format!("Invalid char {} at {}", c, i)),
+                row_length += 1;
+            }
+
+            // Missing cells at the end of a short row are void
+            for x in row_length..w {
+                terrain[y * w + x] = Terrain::Void;
             }
+            row_lengths.push(row_length);
         }
 
-        let mut snake = snake_body.clone();
-        snake.insert(0, snake_head.unwrap());
+        let snake_head = snake_head.ok_or_else(|| "Missing snake head 'h'".to_owned())?;
+        if snake_body.is_empty() {
+            return Err("Missing snake body 'b'".to_owned());
+        }
+        let food = food.ok_or_else(|| "Missing food 'f'".to_owned())?;
 
         let initial_score = 0;
 
-        Ok(Game {
+        let mut game = Game {
+            topology: T::default(),
             width: w,
             height: h,
             snake: Snake {
-                head: snake_head.unwrap(),
+                head: snake_head,
                 body: snake_body,
                 index: 0,
             },
             walls,
-            food: food.unwrap(),
-            previous_direction: Direction::Up,
+            terrain,
+            row_lengths,
+            food,
+            previous_direction: T::INITIAL_DIRECTION,
+            next_direction: T::INITIAL_DIRECTION,
+            accumulator: Duration::ZERO,
             new_piece_to_generate: 0,
+            seed,
+            rng: Rng::with_seed(seed),
+            directions: vec![],
+            observers: vec![],
             last_snapshot: Snapshot {
                 on_food: false,
                 on_wall: false,
                 eat_itself: false,
-                food_position: food.unwrap(),
-                snake,
+                food_position: food,
+                snake: vec![],
                 score: initial_score,
                 period_duration: calculate_period_duration(initial_score),
+                elapsed: Duration::ZERO,
             },
             score: initial_score,
             period_duration: calculate_period_duration(initial_score),
-        })
+            elapsed: Duration::ZERO,
+        };
+        game.order_body();
+        Ok(game)
     }
 }
 
-fn index_to_coordinate(index: usize, w: usize) -> Point {
-    Point {
-        x: index % w,
-        y: index / w,
+/// Reads a level like `SNAKE_1`, one line per row from the top. Lines may
+/// end with `\r\n` and the last one with a newline: the level is read as
+/// if they were `\n` and nothing. The food is placed from a random seed,
+/// or from seed 0 without `std`.
+impl<T: Topology + Default> FromStr for Game<T> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        #[cfg(feature = "std")]
+        let seed = fastrand::u64(..);
+        #[cfg(not(feature = "std"))]
+        let seed = 0;
+        Self::from_str_seeded(s, seed)
+    }
+}
+
+/// Prints the current board with the grammar parsed by [`FromStr`]:
+/// printing a parsed level gives the same text back, with `\n` between the
+/// lines and no trailing newline
+impl<T: Topology> fmt::Display for Game<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, y) in (0..self.height).rev().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for x in 0..self.row_lengths[y] {
+                let point = Point { x, y };
+                let c = if point == self.snake.head {
+                    'h'
+                } else if self.snake.on_body(point) {
+                    'b'
+                } else if point == self.food {
+                    'f'
+                } else {
+                    match self.terrain(point) {
+                        Terrain::Free => ' ',
+                        Terrain::Wall => '#',
+                        Terrain::Void => '.',
+                    }
+                };
+                write!(f, "{}", c)?;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -311,10 +615,39 @@ pub static SNAKE_2: &str = "
     b     
       f   
           ";
+pub static SNAKE_3: &str = "\
+############
+#          #
+#  ##  ##  #
+#     h    #
+#     b  f #
+#  ##  ##  #
+#          #
+############";
+/// Cross shaped: `.` and the missing cells at the end of the rows are void
+pub static SNAKE_4: &str = "\
+....######
+....#    #
+....#    #
+#####    #####
+#     h      #
+#     b  f   #
+#####    #####
+....#    #
+....#    #
+....######";
 
 #[cfg(test)]
 mod tests {
-    use crate::{Direction, Game, Point, SNAKE_2};
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::{
+        observer::GameObserver, Direction, Game, Point, Snake, Terrain, TickOutcome,
+        MAX_ADVANCE_TICKS, SNAKE_1, SNAKE_2, SNAKE_3, SNAKE_4,
+    };
 
     static FIRST_LEVEL: &str = "\
 ##########
@@ -335,6 +668,7 @@ mod tests {
     static UNREACHABLE_POSITION: Point = Point { x: 100, y: 100 };
 
     #[test]
+    #[allow(clippy::redundant_pattern_matching)]
     fn test_ok() {
         let mut game: Game = MY_LEVEL.parse().unwrap();
 
@@ -360,6 +694,64 @@ mod tests {
         assert_eq!(game.food, Point { x: 6, y: 1 });
     }
 
+    #[test]
+    fn test_from_str_seeded() {
+        let mut seeded: Game = Game::from_str_seeded(MY_LEVEL, 7).unwrap();
+        let mut game: Game = MY_LEVEL.parse().unwrap();
+        game.set_seed(7);
+        assert_eq!(seeded.replay().seed, 7);
+
+        // Both place the new food on the same cell
+        seeded.tick(Direction::Up);
+        game.tick(Direction::Up);
+        assert!(seeded.last_snapshot().on_food);
+        assert_eq!(seeded.food, game.food);
+
+        let invalid: Result<Game, _> = Game::from_str_seeded("#h#", 7);
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_from_str_errors() {
+        for (level, error) in [
+            ("#h#\n#x#\n#f#", "Invalid char x at 1,1"),
+            ("#b#\n#f#", "Missing snake head 'h'"),
+            ("#h#\n#b#", "Missing food 'f'"),
+            ("hf", "Missing snake body 'b'"),
+            ("#####\n# hf#\n#####", "Missing snake body 'b'"),
+            ("hh\nbf", "Second snake head at 1,1"),
+            ("hf\nbf", "Second food at 1,1"),
+            ("", "Missing snake head 'h'"),
+        ] {
+            assert_eq!(level.parse::<Game>().err().unwrap(), error, "{}", level);
+        }
+    }
+
+    #[test]
+    fn test_to_string() {
+        for level in [FIRST_LEVEL, MY_LEVEL, SNAKE_1, SNAKE_2, SNAKE_3, SNAKE_4] {
+            let game: Game = level.parse().unwrap();
+            assert_eq!(game.to_string(), level);
+        }
+
+        // The current state, ready to be parsed again
+        let mut game: Game = MY_LEVEL.parse().unwrap();
+        game.set_seed(0);
+        game.tick(Direction::Up);
+        let printed = game.to_string();
+        assert_eq!(printed.lines().nth(1), Some("#   h    #"));
+        let parsed: Game = printed.parse().unwrap();
+        assert_eq!(parsed.snake.head, Point { x: 4, y: 4 });
+        assert_eq!(parsed.food, game.food);
+        assert_eq!(parsed.to_string(), printed);
+
+        // Printed with the line breaks of the parser
+        for level in [SNAKE_1.replace('\n', "\r\n"), format!("{}\n", SNAKE_1)] {
+            let game: Game = level.parse().unwrap();
+            assert_eq!(game.to_string(), SNAKE_1);
+        }
+    }
+
     #[test]
     fn test_tick_up() {
         let mut game: Game = FIRST_LEVEL.parse().unwrap();
@@ -442,12 +834,13 @@ mod tests {
 
         game.tick(crate::Direction::Down);
 
+        // The tail moves first
         assert_eq!(game.snake.head, Point { x: 7, y: 3 });
         assert_eq!(
             game.snake.body,
             vec![
-                Point { x: 4, y: 2 },
                 Point { x: 7, y: 4 },
+                Point { x: 4, y: 3 },
                 Point { x: 4, y: 4 },
                 Point { x: 5, y: 4 },
                 Point { x: 6, y: 4 }
@@ -460,13 +853,49 @@ mod tests {
         assert_eq!(
             game.snake.body,
             vec![
-                Point { x: 4, y: 2 },
                 Point { x: 7, y: 4 },
                 Point { x: 7, y: 3 },
+                Point { x: 4, y: 4 },
                 Point { x: 5, y: 4 },
                 Point { x: 6, y: 4 }
             ]
         );
+        assert_eq!(
+            game.snake.cells(),
+            vec![
+                Point { x: 7, y: 2 },
+                Point { x: 7, y: 3 },
+                Point { x: 7, y: 4 },
+                Point { x: 6, y: 4 },
+                Point { x: 5, y: 4 },
+                Point { x: 4, y: 4 }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_snake_grow_in_ring() {
+        // From the head: (3, 0), (2, 0), (1, 0), (0, 0). The neck is at
+        // `index`, the tail after it, and the ring does not start at the tail.
+        let mut snake = Snake {
+            head: Point { x: 3, y: 0 },
+            body: vec![
+                Point { x: 1, y: 0 },
+                Point { x: 2, y: 0 },
+                Point { x: 0, y: 0 },
+            ],
+            index: 1,
+        };
+
+        // The old head becomes the neck, the tail stays
+        snake.r#move(Point { x: 4, y: 0 }, true);
+        let expected: Vec<Point> = (0..5).rev().map(|x| Point { x, y: 0 }).collect();
+        assert_eq!(snake.cells(), expected);
+
+        // Then the tail moves again
+        snake.r#move(Point { x: 5, y: 0 }, false);
+        let expected: Vec<Point> = (1..6).rev().map(|x| Point { x, y: 0 }).collect();
+        assert_eq!(snake.cells(), expected);
     }
 
     #[test]
@@ -611,6 +1040,203 @@ mod tests {
         assert_eq!(game.snake.head, Point { x: 2, y: 5 });
     }
 
+    #[test]
+    fn test_observer() {
+        #[derive(Clone, Default)]
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+
+        impl GameObserver for Recorder {
+            fn on_food_eaten(&mut self, position: Point) {
+                self.0.lock().unwrap().push(format!("eaten {:?}", position));
+            }
+            fn on_grow(&mut self, length: usize) {
+                self.0.lock().unwrap().push(format!("grow {}", length));
+            }
+            fn on_death(&mut self, reason: &'static str) {
+                self.0.lock().unwrap().push(format!("death {}", reason));
+            }
+            fn on_speed_change(&mut self, period_duration: Duration) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("speed {:?}", period_duration));
+            }
+            fn on_food_spawned(&mut self, position: Point) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("spawned {:?}", position));
+            }
+        }
+
+        let board = "\
+##########
+#        #
+#   f    #
+#   h    #
+#   b    #
+##########";
+        let mut game: Game = board.parse().unwrap();
+        let recorder = Recorder::default();
+        game.add_observer(recorder.clone());
+
+        game.tick(Direction::Up);
+        let food = game.food;
+        assert_eq!(
+            recorder.0.lock().unwrap().drain(..).collect::<Vec<_>>(),
+            vec![
+                format!("eaten {:?}", Point { x: 4, y: 3 }),
+                format!("spawned {:?}", food),
+            ]
+        );
+
+        // The snake grows and eats the food again
+        game.food = Point { x: 4, y: 4 };
+        game.tick(Direction::Up);
+        let food = game.food;
+        assert_eq!(
+            recorder.0.lock().unwrap().drain(..).collect::<Vec<_>>(),
+            vec![
+                "grow 3".to_owned(),
+                format!("eaten {:?}", Point { x: 4, y: 4 }),
+                format!("spawned {:?}", food),
+                format!("speed {:?}", Duration::from_millis(750)),
+            ]
+        );
+
+        game.food = UNREACHABLE_POSITION;
+        game.tick(Direction::Up);
+        assert_eq!(
+            recorder.0.lock().unwrap().drain(..).collect::<Vec<_>>(),
+            vec!["grow 4".to_owned(), "death On Wall".to_owned()]
+        );
+    }
+
+    #[test]
+    fn test_advance() {
+        let mut game: Game = FIRST_LEVEL.parse().unwrap();
+
+        // Not enough time for a tick
+        assert!(game.advance(Duration::from_millis(600)).is_empty());
+        assert_eq!(game.snake.head, Point { x: 4, y: 3 });
+
+        // The time is accumulated
+        let outcomes = game.advance(Duration::from_millis(600));
+        assert_eq!(outcomes.len(), 1);
+        assert!(
+            matches!(&outcomes[0], TickOutcome::Moved(s) if s.snake[0] == Point { x: 4, y: 4 })
+        );
+        assert_eq!(game.accumulator, Duration::from_millis(200));
+
+        game.set_direction(Direction::Right);
+        let outcomes = game.advance(Duration::from_millis(1800));
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(game.snake.head, Point { x: 6, y: 4 });
+
+        // Hit the wall: the remaining time is dropped
+        game.set_direction(Direction::Up);
+        let outcomes = game.advance(Duration::from_secs(10));
+        assert_eq!(outcomes.len(), 1);
+        assert!(matches!(&outcomes[0], TickOutcome::GameOver(s) if s.on_wall));
+        assert!(game.is_over());
+        assert!(game.advance(Duration::from_secs(10)).is_empty());
+    }
+
+    #[test]
+    fn test_advance_cap() {
+        let mut game: Game = SNAKE_2.parse().unwrap();
+        game.set_direction(Direction::Left);
+
+        // A long pause does not run a burst of ticks
+        let outcomes = game.advance(Duration::from_secs(60));
+        assert_eq!(outcomes.len(), MAX_ADVANCE_TICKS);
+        assert_eq!(game.accumulator, Duration::ZERO);
+        assert!(game.advance(Duration::from_millis(10)).is_empty());
+    }
+
+    #[test]
+    fn test_advance_speed_up() {
+        let board = "\
+##########
+#        #
+#        #
+#        #
+#   f    #
+#   h    #
+#   b    #
+##########";
+        let mut game: Game = board.parse().unwrap();
+        game.score = 1;
+        game.set_direction(Direction::Up);
+
+        // The first tick eats the food: the next ones last 750ms
+        let outcomes = game.advance(Duration::from_millis(2500));
+        assert_eq!(outcomes.len(), 3);
+        assert!(!game.is_over());
+        assert_eq!(game.period_duration, Duration::from_millis(750));
+        assert_eq!(game.accumulator, Duration::from_millis(0));
+    }
+
+    #[test]
+    fn test_from_str_sparse() {
+        let game: Game = SNAKE_4.parse().unwrap();
+
+        assert_eq!(game.dim(), (14, 10));
+        // Explicit void
+        let voids = game.voids();
+        assert!(voids.contains(&Point { x: 0, y: 0 }));
+        assert!(voids.contains(&Point { x: 3, y: 9 }));
+        // Missing cells
+        assert!(voids.contains(&Point { x: 10, y: 0 }));
+        assert!(voids.contains(&Point { x: 13, y: 9 }));
+        assert_eq!(voids.len(), 4 * 6 * 2);
+        assert!(game.walls.contains(&Point { x: 13, y: 6 }));
+        assert_eq!(game.snake.head, Point { x: 6, y: 5 });
+        assert_eq!(game.food, Point { x: 9, y: 4 });
+    }
+
+    #[test]
+    fn test_tick_skip_void() {
+        let board = "\
+.. h ..
+.. b ..
+.f
+..   ..";
+        let mut game: Game = board.parse().unwrap();
+        assert_eq!(game.dim(), (7, 4));
+
+        // Top -> Bottom skipping the void
+        game.tick(Direction::Up);
+        assert_eq!(game.snake.head, Point { x: 3, y: 0 });
+
+        // Right -> Left skipping the void on both sides
+        game.tick(Direction::Right);
+        assert_eq!(game.snake.head, Point { x: 4, y: 0 });
+        game.tick(Direction::Right);
+        assert_eq!(game.snake.head, Point { x: 2, y: 0 });
+
+        // Food is never placed on void
+        for _ in 0..50 {
+            let p = game.generate_new_food_position().unwrap();
+            assert_eq!(game.terrain(p), Terrain::Free);
+        }
+    }
+
+    #[test]
+    fn test_food_on_full_board() {
+        let mut game: Game = "#hbf#".parse().unwrap();
+        // The cell of the food is the only free one
+        for _ in 0..10 {
+            assert_eq!(
+                game.generate_new_food_position(),
+                Some(Point { x: 3, y: 0 })
+            );
+        }
+
+        game.snake.body.push(Point { x: 3, y: 0 });
+        assert_eq!(game.generate_new_food_position(), None);
+    }
+
     #[test]
     fn test_box_leak() {
         let game_handler = {
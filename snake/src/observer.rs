@@ -0,0 +1,25 @@
+use core::time::Duration;
+
+use crate::Point;
+
+/// Get notified of what happens in a [`Game`](crate::Game) during a tick.
+///
+/// Every method does nothing by default: implement only the events you are
+/// interested in and register the observer with
+/// [`Game::add_observer`](crate::Game::add_observer).
+pub trait GameObserver: Send + Sync {
+    /// The head of the snake reached the food at `position`
+    fn on_food_eaten(&mut self, _position: Point) {}
+
+    /// The snake got a new piece of body and is now `length` long, head included
+    fn on_grow(&mut self, _length: usize) {}
+
+    /// The snake hit a wall or itself
+    fn on_death(&mut self, _reason: &'static str) {}
+
+    /// The game is faster: a tick lasts `period_duration` now
+    fn on_speed_change(&mut self, _period_duration: Duration) {}
+
+    /// New food appeared at `position`
+    fn on_food_spawned(&mut self, _position: Point) {}
+}
@@ -0,0 +1,126 @@
+use alloc::{format, string::String, vec::Vec};
+use core::{fmt, str::FromStr};
+
+use crate::{Direction, Game};
+
+/// Everything needed to play a game again: the seed used to place the food
+/// and the direction taken at every tick
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub seed: u64,
+    pub directions: Vec<Direction>,
+}
+
+impl Replay {
+    /// Play the replay on `level` and return the game at its last tick
+    pub fn play(&self, level: &str) -> Result<Game, String> {
+        let mut game: Game = level.parse()?;
+        game.set_seed(self.seed);
+
+        for direction in &self.directions {
+            game.tick(*direction);
+        }
+
+        Ok(game)
+    }
+}
+
+/// Written as `<seed>:<directions>` where every direction is one of `UDLR`
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.seed)?;
+        for direction in &self.directions {
+            let c = match direction {
+                Direction::Up => 'U',
+                Direction::Down => 'D',
+                Direction::Left => 'L',
+                Direction::Right => 'R',
+            };
+            write!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (seed, directions) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid replay {}", s))?;
+
+        let seed = seed
+            .parse()
+            .map_err(|_| format!("Invalid replay seed {}", seed))?;
+
+        let directions = directions
+            .chars()
+            .map(|c| match c {
+                'U' => Ok(Direction::Up),
+                'D' => Ok(Direction::Down),
+                'L' => Ok(Direction::Left),
+                'R' => Ok(Direction::Right),
+                _ => Err(format!("Invalid replay direction {}", c)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Replay { seed, directions })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Direction, Game, SNAKE_2};
+
+    use super::Replay;
+
+    #[test]
+    fn test_replay() {
+        let mut game: Game = SNAKE_2.parse().unwrap();
+        game.set_seed(42);
+
+        let directions = [
+            Direction::Up,
+            Direction::Right,
+            Direction::Right,
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+            Direction::Left,
+            Direction::Left,
+        ];
+        for _ in 0..5 {
+            for direction in directions {
+                game.tick(direction);
+            }
+        }
+
+        let replay = game.replay();
+        assert_eq!(replay.seed, 42);
+        assert_eq!(replay.directions.len(), directions.len() * 5);
+
+        let replayed = replay.play(SNAKE_2).unwrap();
+        let expected = game.last_snapshot();
+        let snapshot = replayed.last_snapshot();
+        assert_eq!(snapshot.snake, expected.snake);
+        assert_eq!(snapshot.food_position, expected.food_position);
+        assert_eq!(snapshot.score, expected.score);
+    }
+
+    #[test]
+    fn test_replay_to_string() {
+        let replay = Replay {
+            seed: 7,
+            directions: vec![Direction::Up, Direction::Left, Direction::Down],
+        };
+
+        let s = replay.to_string();
+        assert_eq!(s, "7:ULD");
+        assert_eq!(s.parse::<Replay>().unwrap(), replay);
+
+        assert!("7".parse::<Replay>().is_err());
+        assert!("seven:U".parse::<Replay>().is_err());
+        assert!("7:X".parse::<Replay>().is_err());
+    }
+}
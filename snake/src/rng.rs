@@ -0,0 +1,21 @@
+/// Small seedable random generator (wyrand) so a game can be replayed
+/// from its seed
+#[derive(Debug, Clone)]
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn with_seed(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(crate) fn u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0xA076_1D64_78BD_642F);
+        let t = u128::from(self.0) * u128::from(self.0 ^ 0xE703_7ED1_A0B4_28DB);
+        (t as u64) ^ ((t >> 64) as u64)
+    }
+
+    /// A number in `0..n`
+    pub(crate) fn usize(&mut self, n: usize) -> usize {
+        (self.u64() % n as u64) as usize
+    }
+}
@@ -0,0 +1,281 @@
+use alloc::{
+    borrow::ToOwned,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{cmp::Reverse, fmt, str::FromStr};
+#[cfg(feature = "std")]
+use std::{fs, io, path::PathBuf};
+
+use crate::replay::Replay;
+
+/// How many entries a table keeps
+pub const MAX_ENTRIES: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoreEntry {
+    pub name: String,
+    pub score: usize,
+    /// Length of the snake, head included
+    pub length: usize,
+    /// Seconds since the UNIX epoch
+    pub date: u64,
+    pub replay: Option<Replay>,
+}
+
+/// The best entries of a level, highest score first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HighScoreTable {
+    entries: Vec<ScoreEntry>,
+}
+
+impl HighScoreTable {
+    pub fn entries(&self) -> &[ScoreEntry] {
+        &self.entries
+    }
+
+    /// The position `score` would get in the table, if it gets in at all
+    pub fn rank(&self, score: usize) -> Option<usize> {
+        let rank = self.entries.iter().take_while(|e| e.score >= score).count();
+        if rank < MAX_ENTRIES {
+            Some(rank)
+        } else {
+            None
+        }
+    }
+
+    /// Insert the entry keeping the table sorted.
+    /// Returns its position: `Some(0)` is a new record.
+    pub fn insert(&mut self, entry: ScoreEntry) -> Option<usize> {
+        let rank = self.rank(entry.score)?;
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        Some(rank)
+    }
+}
+
+/// One entry per line: `score`, `length`, `date`, `name` and `replay`
+/// separated by tabs. A missing replay is written as `-`.
+impl fmt::Display for HighScoreTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            let replay = match &entry.replay {
+                Some(replay) => replay.to_string(),
+                None => "-".to_owned(),
+            };
+            // Tabs and new lines would break the format
+            let name = entry.name.replace(['\t', '\n', '\r'], " ");
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{}",
+                entry.score, entry.length, entry.date, name, replay
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for HighScoreTable {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries = vec![];
+        for (i, line) in s.lines().enumerate() {
+            let fields: Vec<_> = line.split('\t').collect();
+            let [score, length, date, name, replay] = fields[..] else {
+                return Err(format!("Invalid score entry at line {}", i));
+            };
+
+            let number = |s: &str| {
+                s.parse::<u64>()
+                    .map_err(|_| format!("Invalid number {} at line {}", s, i))
+            };
+
+            entries.push(ScoreEntry {
+                name: name.to_owned(),
+                score: number(score)? as usize,
+                length: number(length)? as usize,
+                date: number(date)?,
+                replay: match replay {
+                    "-" => None,
+                    replay => Some(replay.parse()?),
+                },
+            });
+        }
+
+        entries.sort_by_key(|e| Reverse(e.score));
+        entries.truncate(MAX_ENTRIES);
+
+        Ok(HighScoreTable { entries })
+    }
+}
+
+/// Where the high score tables are kept
+pub trait ScoreStorage {
+    /// The table of the level. Empty if nothing was saved yet.
+    fn load(&self, level: &str) -> Result<HighScoreTable, String>;
+
+    fn save(&mut self, level: &str, table: &HighScoreTable) -> Result<(), String>;
+
+    /// Add the entry to the table of the level and save it.
+    /// Returns the position of the entry: `Some(0)` is a new record.
+    fn submit(&mut self, level: &str, entry: ScoreEntry) -> Result<Option<usize>, String> {
+        let mut table = self.load(level)?;
+        let rank = table.insert(entry);
+        if rank.is_some() {
+            self.save(level, &table)?;
+        }
+        Ok(rank)
+    }
+}
+
+/// Keep the tables in memory only
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    tables: BTreeMap<String, HighScoreTable>,
+}
+
+impl ScoreStorage for MemoryStorage {
+    fn load(&self, level: &str) -> Result<HighScoreTable, String> {
+        Ok(self.tables.get(level).cloned().unwrap_or_default())
+    }
+
+    fn save(&mut self, level: &str, table: &HighScoreTable) -> Result<(), String> {
+        self.tables.insert(level.to_owned(), table.clone());
+        Ok(())
+    }
+}
+
+/// Keep the tables in a directory, one `<level>.scores` file per level
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct FileStorage {
+    dir: PathBuf,
+}
+
+#[cfg(feature = "std")]
+impl FileStorage {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, level: &str) -> PathBuf {
+        self.dir.join(format!("{}.scores", level))
+    }
+}
+
+#[cfg(feature = "std")]
+impl ScoreStorage for FileStorage {
+    fn load(&self, level: &str) -> Result<HighScoreTable, String> {
+        match fs::read_to_string(self.path(level)) {
+            Ok(content) => content.parse(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HighScoreTable::default()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn save(&mut self, level: &str, table: &HighScoreTable) -> Result<(), String> {
+        fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        fs::write(self.path(level), table.to_string()).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{replay::Replay, Direction};
+
+    use super::{HighScoreTable, MemoryStorage, ScoreEntry, ScoreStorage, MAX_ENTRIES};
+
+    fn entry(name: &str, score: usize) -> ScoreEntry {
+        ScoreEntry {
+            name: name.to_owned(),
+            score,
+            length: score + 2,
+            date: 1_675_000_000,
+            replay: None,
+        }
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut table = HighScoreTable::default();
+
+        assert_eq!(table.insert(entry("a", 5)), Some(0));
+        assert_eq!(table.insert(entry("b", 3)), Some(1));
+        assert_eq!(table.insert(entry("c", 8)), Some(0));
+        // Same score: the older entry stays in front
+        assert_eq!(table.insert(entry("d", 5)), Some(2));
+
+        let names: Vec<_> = table.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["c", "a", "d", "b"]);
+
+        for i in 0..MAX_ENTRIES {
+            table.insert(entry("e", 10 + i));
+        }
+        assert_eq!(table.entries().len(), MAX_ENTRIES);
+        assert_eq!(table.rank(1), None);
+        assert_eq!(table.insert(entry("f", 1)), None);
+    }
+
+    #[test]
+    fn test_to_string() {
+        let mut table = HighScoreTable::default();
+        table.insert(entry("my\tname", 5));
+        table.insert(ScoreEntry {
+            replay: Some(Replay {
+                seed: 3,
+                directions: vec![Direction::Up, Direction::Right],
+            }),
+            ..entry("other", 2)
+        });
+
+        let s = table.to_string();
+        assert_eq!(
+            s,
+            "5\t7\t1675000000\tmy name\t-\n2\t4\t1675000000\tother\t3:UR\n"
+        );
+
+        let parsed: HighScoreTable = s.parse().unwrap();
+        assert_eq!(parsed.entries()[0].name, "my name");
+        assert_eq!(parsed.entries()[1], table.entries()[1]);
+
+        assert!("5\t7".parse::<HighScoreTable>().is_err());
+        assert!("five\t7\t0\tname\t-".parse::<HighScoreTable>().is_err());
+    }
+
+    #[test]
+    fn test_memory_storage() {
+        let mut storage = MemoryStorage::default();
+
+        assert!(storage.load("snake1").unwrap().entries().is_empty());
+        assert_eq!(storage.submit("snake1", entry("a", 5)).unwrap(), Some(0));
+        assert_eq!(storage.submit("snake1", entry("b", 3)).unwrap(), Some(1));
+        assert_eq!(storage.submit("snake2", entry("c", 1)).unwrap(), Some(0));
+
+        assert_eq!(storage.load("snake1").unwrap().entries().len(), 2);
+        assert_eq!(storage.load("snake2").unwrap().entries().len(), 1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_file_storage() {
+        use super::FileStorage;
+
+        let dir = std::env::temp_dir().join(format!("snake-scores-{}", std::process::id()));
+        let mut storage = FileStorage::new(&dir);
+
+        assert!(storage.load("snake1").unwrap().entries().is_empty());
+        assert_eq!(storage.submit("snake1", entry("a", 5)).unwrap(), Some(0));
+        assert_eq!(storage.submit("snake1", entry("b", 7)).unwrap(), Some(0));
+
+        let storage = FileStorage::new(&dir);
+        let table = storage.load("snake1").unwrap();
+        assert_eq!(table.entries()[0].name, "b");
+        assert_eq!(table.entries()[1].name, "a");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
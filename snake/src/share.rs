@@ -0,0 +1,284 @@
+//! Levels and replays as short, URL-safe codes, to put in a link.
+//!
+//! A code is the base64url (without padding) of:
+//! - the format version, one byte;
+//! - the length of the level, as a varint, then the level: every byte is a
+//!   char of the level (3 bits) and how many times it repeats (5 bits);
+//! - only with a replay: the seed as a varint, then the directions: every
+//!   byte is a direction (2 bits) and how many times it repeats (6 bits).
+
+use alloc::{format, string::String, vec::Vec};
+use core::{fmt, str::FromStr};
+
+use crate::{replay::Replay, Direction, Game};
+
+const VERSION: u8 = 1;
+
+/// The chars of a level, by their 3 bits code
+const LEVEL_CHARS: [char; 7] = [' ', '#', '.', 'h', 'b', 'f', '\n'];
+const MAX_LEVEL_RUN: usize = 1 << 5;
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+const MAX_DIRECTION_RUN: usize = 1 << 6;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// A level to share, with a game to watch on it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Share {
+    level: String,
+    /// The level as written in the code
+    encoded_level: Vec<u8>,
+    replay: Option<Replay>,
+}
+
+impl Share {
+    /// Fails if `level` cannot be played. The lines are kept as the parser
+    /// reads them: `\r\n` becomes `\n` and a trailing newline is dropped.
+    pub fn new(level: String, replay: Option<Replay>) -> Result<Self, String> {
+        level.parse::<Game>()?;
+        let level = level.lines().collect::<Vec<_>>().join("\n");
+
+        let mut encoded_level = Vec::new();
+        for (c, run) in runs(level.chars(), MAX_LEVEL_RUN) {
+            let code = LEVEL_CHARS
+                .iter()
+                .position(|l| *l == c)
+                .ok_or_else(|| format!("Cannot share the char {:?}", c))?;
+            encoded_level.push(code as u8 | ((run - 1) as u8) << 3);
+        }
+
+        Ok(Self {
+            level,
+            encoded_level,
+            replay,
+        })
+    }
+
+    /// Written like `SNAKE_1`
+    pub fn level(&self) -> &str {
+        &self.level
+    }
+
+    pub fn replay(&self) -> Option<&Replay> {
+        self.replay.as_ref()
+    }
+}
+
+impl fmt::Display for Share {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut bytes = Vec::new();
+        bytes.push(VERSION);
+
+        write_varint(&mut bytes, self.encoded_level.len() as u64);
+        bytes.extend(&self.encoded_level);
+
+        if let Some(replay) = &self.replay {
+            write_varint(&mut bytes, replay.seed);
+            for (direction, run) in runs(replay.directions.iter().copied(), MAX_DIRECTION_RUN) {
+                bytes.push(direction_code(direction) | ((run - 1) as u8) << 2);
+            }
+        }
+
+        f.write_str(&encode_base64(&bytes))
+    }
+}
+
+impl FromStr for Share {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_base64(s)?;
+        let mut bytes = bytes.iter().copied();
+
+        match bytes.next() {
+            Some(VERSION) => {}
+            Some(version) => return Err(format!("Unknown share code version {}", version)),
+            None => return Err("Empty share code".into()),
+        }
+
+        let len = read_varint(&mut bytes)? as usize;
+        let mut level = String::new();
+        for _ in 0..len {
+            let byte = bytes.next().ok_or("Share code too short")?;
+            let c = LEVEL_CHARS
+                .get((byte & 0b111) as usize)
+                .ok_or("Invalid level in share code")?;
+            level.extend(core::iter::repeat_n(*c, (byte >> 3) as usize + 1));
+        }
+
+        let mut bytes = bytes.peekable();
+        let replay = if bytes.peek().is_some() {
+            let seed = read_varint(&mut bytes)?;
+            let mut directions = Vec::new();
+            for byte in bytes {
+                let direction = DIRECTIONS[(byte & 0b11) as usize];
+                directions.extend(core::iter::repeat_n(direction, (byte >> 2) as usize + 1));
+            }
+            Some(Replay { seed, directions })
+        } else {
+            None
+        };
+
+        Share::new(level, replay)
+    }
+}
+
+/// The index of `direction` in `DIRECTIONS`
+fn direction_code(direction: Direction) -> u8 {
+    match direction {
+        Direction::Up => 0,
+        Direction::Down => 1,
+        Direction::Left => 2,
+        Direction::Right => 3,
+    }
+}
+
+/// Every item with how many times it repeats, at most `max`
+fn runs<T: PartialEq>(items: impl Iterator<Item = T>, max: usize) -> Vec<(T, usize)> {
+    let mut runs: Vec<(T, usize)> = Vec::new();
+    for item in items {
+        match runs.last_mut() {
+            Some((last, run)) if *last == item && *run < max => *run += 1,
+            _ => runs.push((item, 1)),
+        }
+    }
+    runs
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        bytes.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    bytes.push(n as u8);
+}
+
+fn read_varint(bytes: &mut impl Iterator<Item = u8>) -> Result<u64, String> {
+    let mut n = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = bytes.next().ok_or("Share code too short")?;
+        n |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err("Invalid number in share code".into())
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut s = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            s.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    s
+}
+
+fn decode_base64(s: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    for chunk in s.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return Err("Share code too short".into());
+        }
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = BASE64
+                .iter()
+                .position(|b| b == c)
+                .ok_or_else(|| format!("Invalid char {} in share code", *c as char))?;
+            n |= (value as u32) << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            bytes.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{replay::Replay, Direction, Game, SNAKE_1, SNAKE_2, SNAKE_4};
+
+    use super::{decode_base64, encode_base64, Share};
+
+    #[test]
+    fn test_base64() {
+        for bytes in [&b""[..], b"f", b"fo", b"foo", b"foob", b"\xff\xfe\x00"] {
+            let s = encode_base64(bytes);
+            assert!(s
+                .bytes()
+                .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+            assert_eq!(decode_base64(&s).unwrap(), bytes);
+        }
+        assert_eq!(encode_base64(b"foob"), "Zm9vYg");
+        assert!(decode_base64("Zm9vY").is_err());
+        assert!(decode_base64("Zm9=").is_err());
+    }
+
+    #[test]
+    fn test_share_level() {
+        for level in [SNAKE_1, SNAKE_2, SNAKE_4] {
+            let share = Share::new(level.into(), None).unwrap();
+            let code = share.to_string();
+            assert!(code.len() < level.len());
+            assert_eq!(code.parse::<Share>().unwrap(), share);
+        }
+
+        // Shared as the parser reads them
+        for level in [
+            "#####\r\n#h  #\r\n#b f#\r\n#####",
+            "#####\n#h  #\n#b f#\n#####\n",
+        ] {
+            let share = Share::new(level.into(), None).unwrap();
+            assert_eq!(share.level(), "#####\n#h  #\n#b f#\n#####");
+            assert_eq!(share.to_string().parse::<Share>().unwrap(), share);
+        }
+    }
+
+    #[test]
+    fn test_share_replay() {
+        let mut game: Game = SNAKE_2.parse().unwrap();
+        game.set_seed(u64::MAX);
+        for direction in [Direction::Up, Direction::Right, Direction::Down] {
+            for _ in 0..70 {
+                game.tick(direction);
+            }
+        }
+
+        let share = Share::new(SNAKE_2.into(), Some(game.replay())).unwrap();
+        let decoded: Share = share.to_string().parse().unwrap();
+        assert_eq!(decoded, share);
+
+        let replayed = decoded.replay().unwrap().play(decoded.level()).unwrap();
+        assert_eq!(replayed.last_snapshot().snake, game.last_snapshot().snake);
+
+        let replay = Replay {
+            seed: 0,
+            directions: vec![],
+        };
+        let empty = Share::new("hbf".into(), Some(replay)).unwrap();
+        assert_eq!(empty.to_string().parse::<Share>().unwrap(), empty);
+    }
+
+    #[test]
+    fn test_invalid_share() {
+        assert!(Share::new("hb".into(), None).is_err());
+        // A lone `\r` is not a line break
+        assert!(Share::new("hbf\r#".into(), None).is_err());
+        assert!("".parse::<Share>().is_err());
+        assert!("Ag".parse::<Share>().is_err());
+        assert!("AQ!".parse::<Share>().is_err());
+        assert!("AQo".parse::<Share>().is_err());
+    }
+}
@@ -0,0 +1,462 @@
+//! Stream a game to spectators: the board first, then a frame per tick.
+//!
+//! Every frame is a line of text. Spectators joining late get a keyframe
+//! with the whole snapshot, the others only what changed since the previous
+//! tick.
+
+use alloc::{borrow::ToOwned, format, string::String, vec, vec::Vec};
+use core::{fmt, str::FromStr, time::Duration};
+
+use crate::{topology::Topology, Game, Point, Snapshot};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    /// What never changes: sent before any other frame
+    Board {
+        width: usize,
+        height: usize,
+        walls: Vec<Point>,
+        voids: Vec<Point>,
+    },
+    /// The whole snapshot of the tick
+    Key { tick: u64, snapshot: Snapshot },
+    /// The snapshot of the tick without the body of the snake:
+    /// `snapshot.snake` only has the head. The body is the previous one
+    /// with `added` before its first cell and without `removed`, its last
+    /// cells, so it stays in order from the neck to the tail.
+    Delta {
+        tick: u64,
+        snapshot: Snapshot,
+        added: Vec<Point>,
+        removed: Vec<Point>,
+    },
+}
+
+/// Turns the snapshots of a game into frames
+pub struct Encoder {
+    board: Frame,
+    tick: u64,
+    last: Snapshot,
+}
+
+impl Encoder {
+    pub fn new<T: Topology>(game: &Game<T>) -> Self {
+        let (width, height) = game.dim();
+        Self {
+            board: Frame::Board {
+                width,
+                height,
+                walls: game.walls(),
+                voids: game.voids(),
+            },
+            tick: 0,
+            last: game.last_snapshot(),
+        }
+    }
+
+    /// What a spectator needs when it joins: the board and a keyframe
+    pub fn catch_up(&self) -> [Frame; 2] {
+        [
+            self.board.clone(),
+            Frame::Key {
+                tick: self.tick,
+                snapshot: self.last.clone(),
+            },
+        ]
+    }
+
+    /// The delta from the previous snapshot
+    pub fn push(&mut self, snapshot: Snapshot) -> Frame {
+        let (added, removed) = delta(&self.last.snake[1..], &snapshot.snake[1..]);
+
+        self.tick += 1;
+        self.last = snapshot.clone();
+
+        Frame::Delta {
+            tick: self.tick,
+            snapshot: Snapshot {
+                snake: vec![snapshot.snake[0]],
+                ..snapshot
+            },
+            added,
+            removed,
+        }
+    }
+}
+
+/// The game as known by a spectator, rebuilt from the frames
+#[derive(Debug, Clone, Default)]
+pub struct View {
+    dim: (usize, usize),
+    walls: Vec<Point>,
+    voids: Vec<Point>,
+    tick: u64,
+    snapshot: Option<Snapshot>,
+}
+
+impl View {
+    pub fn apply(&mut self, frame: Frame) -> Result<(), String> {
+        match frame {
+            Frame::Board {
+                width,
+                height,
+                walls,
+                voids,
+            } => {
+                *self = View {
+                    dim: (width, height),
+                    walls,
+                    voids,
+                    ..View::default()
+                };
+            }
+            Frame::Key { tick, snapshot } => {
+                self.tick = tick;
+                self.snapshot = Some(snapshot);
+            }
+            Frame::Delta {
+                tick,
+                mut snapshot,
+                added,
+                removed,
+            } => {
+                let last = self
+                    .snapshot
+                    .as_ref()
+                    .ok_or_else(|| "Delta before the keyframe".to_owned())?;
+                if tick != self.tick + 1 {
+                    return Err(format!("Expected tick {}, got {}", self.tick + 1, tick));
+                }
+
+                let body = &last.snake[1..];
+                let kept = body
+                    .len()
+                    .checked_sub(removed.len())
+                    .filter(|kept| body[*kept..] == removed[..])
+                    .ok_or_else(|| format!("Invalid delta at tick {}", tick))?;
+                snapshot.snake.extend(added);
+                snapshot.snake.extend_from_slice(&body[..kept]);
+
+                self.tick = tick;
+                self.snapshot = Some(snapshot);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn dim(&self) -> (usize, usize) {
+        self.dim
+    }
+
+    pub fn walls(&self) -> &[Point] {
+        &self.walls
+    }
+
+    pub fn voids(&self) -> &[Point] {
+        &self.voids
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// `None` till the keyframe arrives
+    pub fn snapshot(&self) -> Option<&Snapshot> {
+        self.snapshot.as_ref()
+    }
+}
+
+/// The cells added before `last` and removed from its end to get `body`,
+/// removing as few as possible: one of each when the snake moves
+fn delta(last: &[Point], body: &[Point]) -> (Vec<Point>, Vec<Point>) {
+    let removed = (0..=last.len())
+        .find(|removed| {
+            let kept = &last[..last.len() - removed];
+            kept.len() <= body.len() && body.ends_with(kept)
+        })
+        .unwrap_or(last.len());
+    let kept = last.len() - removed;
+    (body[..body.len() - kept].to_vec(), last[kept..].to_vec())
+}
+
+/// Points are `x,y`, separated by `;`. No points is `-`.
+struct Points<'a>(&'a [Point]);
+
+impl fmt::Display for Points<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "-");
+        }
+        for (i, p) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ";")?;
+            }
+            write!(f, "{},{}", p.x, p.y)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_number<N: FromStr>(s: &str) -> Result<N, String> {
+    s.parse().map_err(|_| format!("Invalid number {}", s))
+}
+
+fn parse_points(s: &str) -> Result<Vec<Point>, String> {
+    if s == "-" {
+        return Ok(vec![]);
+    }
+    s.split(';')
+        .map(|p| {
+            let (x, y) = p
+                .split_once(',')
+                .ok_or_else(|| format!("Invalid point {}", p))?;
+            Ok(Point {
+                x: parse_number(x)?,
+                y: parse_number(y)?,
+            })
+        })
+        .collect()
+}
+
+/// `<score>:<flags>:<food>:<period>:<elapsed>` where the flags are
+/// `on_food`, `on_wall` and `eat_itself` as `0` or `1` and the durations
+/// are in milliseconds
+fn write_state(f: &mut fmt::Formatter<'_>, snapshot: &Snapshot) -> fmt::Result {
+    write!(
+        f,
+        "{}:{}{}{}:{}:{}:{}",
+        snapshot.score,
+        snapshot.on_food as u8,
+        snapshot.on_wall as u8,
+        snapshot.eat_itself as u8,
+        Points(&[snapshot.food_position]),
+        snapshot.period_duration.as_millis(),
+        snapshot.elapsed.as_millis(),
+    )
+}
+
+fn parse_state(s: &str, snake: Vec<Point>) -> Result<Snapshot, String> {
+    let fields: Vec<_> = s.split(':').collect();
+    let [score, flags, food, period, elapsed] = fields[..] else {
+        return Err(format!("Invalid state {}", s));
+    };
+    let flags: Vec<_> = flags.chars().map(|c| c == '1').collect();
+    let [on_food, on_wall, eat_itself] = flags[..] else {
+        return Err(format!("Invalid flags {}", s));
+    };
+    let food_position = match parse_points(food)?[..] {
+        [food] => food,
+        _ => return Err(format!("Invalid food {}", food)),
+    };
+
+    Ok(Snapshot {
+        on_food,
+        on_wall,
+        eat_itself,
+        food_position,
+        snake,
+        score: parse_number(score)?,
+        period_duration: Duration::from_millis(parse_number(period)?),
+        elapsed: Duration::from_millis(parse_number(elapsed)?),
+    })
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Frame::Board {
+                width,
+                height,
+                walls,
+                voids,
+            } => write!(
+                f,
+                "BOARD {} {} {} {}",
+                width,
+                height,
+                Points(walls),
+                Points(voids)
+            ),
+            Frame::Key { tick, snapshot } => {
+                write!(f, "KEY {} ", tick)?;
+                write_state(f, snapshot)?;
+                write!(f, " {}", Points(&snapshot.snake))
+            }
+            Frame::Delta {
+                tick,
+                snapshot,
+                added,
+                removed,
+            } => {
+                write!(f, "DELTA {} ", tick)?;
+                write_state(f, snapshot)?;
+                write!(
+                    f,
+                    " {} {} {}",
+                    Points(&snapshot.snake[..1]),
+                    Points(added),
+                    Points(removed)
+                )
+            }
+        }
+    }
+}
+
+impl FromStr for Frame {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let args: Vec<_> = s.split(' ').collect();
+        match args[..] {
+            ["BOARD", width, height, walls, voids] => Ok(Frame::Board {
+                width: parse_number(width)?,
+                height: parse_number(height)?,
+                walls: parse_points(walls)?,
+                voids: parse_points(voids)?,
+            }),
+            ["KEY", tick, state, snake] => {
+                let snake = parse_points(snake)?;
+                if snake.is_empty() {
+                    return Err(format!("Invalid snake {}", s));
+                }
+                Ok(Frame::Key {
+                    tick: parse_number(tick)?,
+                    snapshot: parse_state(state, snake)?,
+                })
+            }
+            ["DELTA", tick, state, head, added, removed] => {
+                let head = parse_points(head)?;
+                if head.len() != 1 {
+                    return Err(format!("Invalid head {}", s));
+                }
+                Ok(Frame::Delta {
+                    tick: parse_number(tick)?,
+                    snapshot: parse_state(state, head)?,
+                    added: parse_points(added)?,
+                    removed: parse_points(removed)?,
+                })
+            }
+            _ => Err(format!("Invalid frame {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Direction, Game, Point, SNAKE_1, SNAKE_4};
+
+    use super::{delta, Encoder, Frame, View};
+
+    #[test]
+    fn test_stream() {
+        let mut game: Game = SNAKE_1.parse().unwrap();
+        game.set_seed(3);
+        let mut encoder = Encoder::new(&game);
+
+        let mut view = View::default();
+        let mut late = None;
+
+        let directions = [
+            Direction::Right,
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+        ];
+        for (i, direction) in directions.iter().cycle().take(40).enumerate() {
+            if i == 10 {
+                // Joins late
+                let mut late_view = View::default();
+                for frame in encoder.catch_up() {
+                    late_view.apply(frame).unwrap();
+                }
+                late = Some(late_view);
+            }
+            if i == 0 {
+                for frame in encoder.catch_up() {
+                    view.apply(frame).unwrap();
+                }
+            }
+
+            game.tick(*direction);
+            let frame = encoder.push(game.last_snapshot());
+
+            // Every frame goes through the wire
+            let frame: Frame = frame.to_string().parse().unwrap();
+            view.apply(frame.clone()).unwrap();
+            if let Some(late) = &mut late {
+                late.apply(frame).unwrap();
+            }
+
+            let expected = game.last_snapshot();
+            for view in [Some(&view), late.as_ref()].into_iter().flatten() {
+                let snapshot = view.snapshot().unwrap();
+                assert_eq!(snapshot.snake, expected.snake);
+                assert_eq!(snapshot.food_position, expected.food_position);
+                assert_eq!(snapshot.score, expected.score);
+                assert_eq!(view.tick(), i as u64 + 1);
+            }
+        }
+
+        assert_eq!(view.dim(), game.dim());
+        assert_eq!(view.walls(), &game.walls()[..]);
+    }
+
+    #[test]
+    fn test_delta() {
+        let p = |x, y| Point { x, y };
+        let last = [p(2, 1), p(1, 1), p(0, 1)];
+
+        // Moving: the old head is added, the tail removed
+        let moved = [p(3, 1), p(2, 1), p(1, 1)];
+        assert_eq!(delta(&last, &moved), (vec![p(3, 1)], vec![p(0, 1)]));
+        // Growing: the tail stays
+        let grown = [p(3, 1), p(2, 1), p(1, 1), p(0, 1)];
+        assert_eq!(delta(&last, &grown), (vec![p(3, 1)], vec![]));
+        // Anything else: the whole body
+        let other = [p(5, 5)];
+        assert_eq!(delta(&last, &other), (other.to_vec(), last.to_vec()));
+
+        // The removed cells must be the tail of the body
+        let mut game: Game = SNAKE_4.parse().unwrap();
+        game.set_seed(0);
+        let mut view = View::default();
+        for frame in Encoder::new(&game).catch_up() {
+            view.apply(frame).unwrap();
+        }
+        let delta = "DELTA 1 0:000:9,4:1000:1000 6,6 6,5 6,3".parse().unwrap();
+        assert!(view.apply(delta).is_err());
+        let delta = "DELTA 1 0:000:9,4:1000:1000 6,6 6,5 6,4".parse().unwrap();
+        view.apply(delta).unwrap();
+        assert_eq!(view.snapshot().unwrap().snake, [p(6, 6), p(6, 5)]);
+    }
+
+    #[test]
+    fn test_frame() {
+        let game: Game = SNAKE_4.parse().unwrap();
+        let encoder = Encoder::new(&game);
+
+        for frame in encoder.catch_up() {
+            assert_eq!(frame.to_string().parse::<Frame>().unwrap(), frame);
+        }
+
+        let [board, key] = encoder.catch_up();
+        assert!(board.to_string().starts_with("BOARD 14 10 "));
+        assert_eq!(key.to_string(), "KEY 0 0:000:9,4:1000:0 6,5;6,4");
+
+        // No delta before the keyframe
+        let mut view = View::default();
+        view.apply(board).unwrap();
+        let delta = "DELTA 1 0:000:9,4:1000:1000 6,6 - -".parse().unwrap();
+        assert!(view.apply(delta).is_err());
+
+        for invalid in [
+            "",
+            "KEY 0",
+            "KEY 0 0:000:1,1:0:0 -",
+            "KEY 0 0:00:1,1:0:0 1,1",
+            "DELTA 1 0:000:1,1:0:0 - - -",
+        ] {
+            assert!(invalid.parse::<Frame>().is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Check the invariants of the engine on random boards and moves.
+//!
+//! Enabled by the `testing` feature. Crates adding rules on top of the engine
+//! can run their games through [`Invariants`] too.
+
+use alloc::{format, string::String, vec, vec::Vec};
+use core::time::Duration;
+
+use crate::{rng::Rng, topology::Topology, Direction, Game, Point, Terrain};
+
+/// A random board that can be parsed: walls, voids, a snake going up and
+/// some food. The body, one cell up to half the width long, goes down from
+/// the head then left. Always the same board for the same seed.
+pub fn random_level(seed: u64) -> String {
+    let mut rng = Rng::with_seed(seed);
+    let width = 4 + rng.usize(13);
+    let height = 4 + rng.usize(13);
+
+    let mut cells = vec![vec![' '; width]; height];
+    for cell in cells.iter_mut().flatten() {
+        *cell = match rng.usize(20) {
+            0..=2 => '#',
+            3 => '.',
+            _ => ' ',
+        };
+    }
+
+    // The snake goes up from the middle of the board, the food on its right
+    let (x, y) = (width / 2, height / 2);
+    cells[y][x] = 'h';
+    let length = 1 + rng.usize(width / 2);
+    for cell in &mut cells[y + 1][x + 1 - length..=x] {
+        *cell = 'b';
+    }
+    cells[y][(x + 1) % width] = 'f';
+
+    let lines: Vec<String> = cells
+        .into_iter()
+        .map(|row| row.into_iter().collect())
+        .collect();
+    lines.join("\n")
+}
+
+/// `len` random directions. Always the same for the same seed.
+pub fn random_directions(seed: u64, len: usize) -> Vec<Direction> {
+    let mut rng = Rng::with_seed(seed);
+    (0..len)
+        .map(|_| match rng.usize(4) {
+            0 => Direction::Up,
+            1 => Direction::Down,
+            2 => Direction::Left,
+            _ => Direction::Right,
+        })
+        .collect()
+}
+
+/// What must hold after every tick, given what happened before
+pub struct Invariants {
+    initial_length: usize,
+    eaten: usize,
+    score: usize,
+    period_duration: Duration,
+}
+
+impl Invariants {
+    pub fn new<T: Topology>(game: &Game<T>) -> Self {
+        let snapshot = game.last_snapshot();
+        Self {
+            initial_length: snapshot.snake.len(),
+            eaten: 0,
+            score: snapshot.score,
+            period_duration: snapshot.period_duration,
+        }
+    }
+
+    /// Call it after every tick
+    pub fn check<T: Topology<Direction = Direction>>(
+        &mut self,
+        game: &Game<T>,
+    ) -> Result<(), String> {
+        let snapshot = game.last_snapshot();
+        if snapshot.on_food {
+            self.eaten += 1;
+        }
+
+        let snake = game.snake.cells();
+        for pair in snake.windows(2) {
+            if !game.are_neighbours(pair[0], pair[1]) {
+                return Err(format!("Snake not contiguous: {:?}", snake));
+            }
+        }
+
+        // The snake grows the tick after eating
+        let length = self.initial_length + self.eaten - game.new_piece_to_generate;
+        if snake.len() != length {
+            return Err(format!(
+                "Snake is {} cells long, expected {}",
+                snake.len(),
+                length
+            ));
+        }
+
+        let food = snapshot.food_position;
+        if game.terrain(food) != Terrain::Free {
+            return Err(format!("Food on a wall or void: {:?}", food));
+        }
+        // A full board keeps the food where it was eaten
+        if game.snake.contains(food) && !game.free_cells().is_empty() {
+            return Err(format!("Food on the snake: {:?}", food));
+        }
+
+        if snapshot.score < self.score {
+            return Err(format!("Score went down to {}", snapshot.score));
+        }
+        self.score = snapshot.score;
+
+        if snapshot.period_duration > self.period_duration {
+            return Err(format!("Period went up to {:?}", snapshot.period_duration));
+        }
+        self.period_duration = snapshot.period_duration;
+
+        Ok(())
+    }
+}
+
+/// Play `directions` on `level` checking the invariants after every tick,
+/// till the game is over
+pub fn check_game<T: Topology<Direction = Direction> + Default>(
+    level: &str,
+    seed: u64,
+    directions: &[Direction],
+) -> Result<(), String> {
+    let mut game: Game<T> = level.parse()?;
+    game.set_seed(seed);
+    let mut invariants = Invariants::new(&game);
+
+    for (i, direction) in directions.iter().enumerate() {
+        if game.is_over() {
+            break;
+        }
+        game.tick(*direction);
+        invariants
+            .check(&game)
+            .map_err(|e| format!("Tick {}: {}", i + 1, e))?;
+    }
+
+    Ok(())
+}
+
+/// Run `cases` random games from `seed`.
+/// The error tells the level and the directions that broke an invariant.
+pub fn check_random<T: Topology<Direction = Direction> + Default>(
+    seed: u64,
+    cases: u64,
+) -> Result<(), String> {
+    for case in seed..seed + cases {
+        let level = random_level(case);
+        // Few moves: the food needs a free cell to spawn on
+        let free = level.chars().filter(|c| *c == ' ').count();
+        let directions = random_directions(case, free / 2);
+
+        check_game::<T>(&level, case, &directions).map_err(|e| {
+            format!(
+                "Case {}: {}\nlevel:\n{}\ndirections: {:?}",
+                case, e, level, directions
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+impl<T: Topology<Direction = Direction>> Game<T> {
+    /// Whether one of the moves from `a` reaches `b`
+    fn are_neighbours(&self, a: Point, b: Point) -> bool {
+        T::DIRECTIONS
+            .iter()
+            .any(|d| self.next_cell(a, *d) == Some(b) || self.next_cell(b, *d) == Some(a))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        topology::{Bounded, Klein, Mobius, Torus},
+        Game,
+    };
+
+    use super::{check_random, random_directions, random_level};
+
+    #[test]
+    fn test_random_level() {
+        for seed in 0..100 {
+            let level = random_level(seed);
+            assert_eq!(level, random_level(seed));
+            assert!(level.parse::<Game>().is_ok(), "{}", level);
+        }
+        assert!((0..100).any(|seed| random_level(seed).matches('b').count() > 2));
+        assert_eq!(random_directions(3, 10), random_directions(3, 10));
+    }
+
+    #[test]
+    fn test_invariants() {
+        check_random::<Torus>(0, 500).unwrap();
+        check_random::<Bounded>(0, 500).unwrap();
+        check_random::<Mobius>(0, 500).unwrap();
+        check_random::<Klein>(0, 500).unwrap();
+    }
+}
@@ -0,0 +1,374 @@
+use alloc::{format, string::String};
+use core::fmt::Debug;
+
+use crate::{Direction, Point};
+
+/// How the cells of a board are connected
+pub trait Topology {
+    /// The directions the snake can take
+    type Direction: Copy + Debug + PartialEq + Send + Sync + 'static;
+
+    /// The direction of the snake when the game starts
+    const INITIAL_DIRECTION: Self::Direction;
+
+    /// Every direction, to find the neighbours of a cell
+    const DIRECTIONS: &'static [Self::Direction];
+
+    /// Whether the cells are hexagons, so renderers can lay them out
+    const HEXAGONAL: bool = false;
+
+    /// Fails if a `width` x `height` board cannot be built
+    fn check_size(_width: usize, _height: usize) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// The cell reached moving from `point` towards `direction` on a
+    /// `width` x `height` board. `None` if the move leaves the board.
+    fn neighbour(
+        &self,
+        point: Point,
+        direction: Self::Direction,
+        width: usize,
+        height: usize,
+    ) -> Option<Point>;
+
+    /// The snake cannot turn back on itself
+    fn is_opposite(a: Self::Direction, b: Self::Direction) -> bool;
+}
+
+/// Square grid: leaving the board is like hitting a wall
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bounded;
+
+/// Square grid where every edge wraps onto the opposite one
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Torus;
+
+/// Square grid where the left and right edges wrap upside down.
+/// Top and bottom edges are bounded.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mobius;
+
+/// Square grid where the left and right edges wrap upside down
+/// and the top and bottom edges wrap normally
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Klein;
+
+/// Hexagonal grid in "odd-r" layout: odd rows are shifted half a cell to
+/// the right. Every edge wraps onto the opposite one, so the height must
+/// be even.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Hex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexDirection {
+    UpLeft,
+    UpRight,
+    Left,
+    Right,
+    DownLeft,
+    DownRight,
+}
+
+const SQUARE_DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+fn square_step(point: Point, direction: Direction) -> (isize, isize) {
+    let (x, y) = (point.x as isize, point.y as isize);
+    match direction {
+        Direction::Up => (x, y + 1),
+        Direction::Down => (x, y - 1),
+        Direction::Left => (x - 1, y),
+        Direction::Right => (x + 1, y),
+    }
+}
+
+fn wrap(v: isize, max: usize) -> usize {
+    v.rem_euclid(max as isize) as usize
+}
+
+fn bounded(v: isize, max: usize) -> Option<usize> {
+    if (0..max as isize).contains(&v) {
+        Some(v as usize)
+    } else {
+        None
+    }
+}
+
+/// Wrap `x` flipping `y` when the left or right edge is crossed
+fn twisted(x: isize, y: usize, width: usize, height: usize) -> (usize, usize) {
+    if (0..width as isize).contains(&x) {
+        (x as usize, y)
+    } else {
+        (wrap(x, width), height - 1 - y)
+    }
+}
+
+impl Topology for Bounded {
+    type Direction = Direction;
+
+    const INITIAL_DIRECTION: Direction = Direction::Up;
+
+    const DIRECTIONS: &'static [Direction] = &SQUARE_DIRECTIONS;
+
+    fn neighbour(&self, point: Point, direction: Direction, w: usize, h: usize) -> Option<Point> {
+        let (x, y) = square_step(point, direction);
+        Some(Point {
+            x: bounded(x, w)?,
+            y: bounded(y, h)?,
+        })
+    }
+
+    fn is_opposite(a: Direction, b: Direction) -> bool {
+        !a.is_compatible_with(b)
+    }
+}
+
+impl Topology for Torus {
+    type Direction = Direction;
+
+    const INITIAL_DIRECTION: Direction = Direction::Up;
+
+    const DIRECTIONS: &'static [Direction] = &SQUARE_DIRECTIONS;
+
+    fn neighbour(&self, point: Point, direction: Direction, w: usize, h: usize) -> Option<Point> {
+        let (x, y) = square_step(point, direction);
+        Some(Point {
+            x: wrap(x, w),
+            y: wrap(y, h),
+        })
+    }
+
+    fn is_opposite(a: Direction, b: Direction) -> bool {
+        !a.is_compatible_with(b)
+    }
+}
+
+impl Topology for Mobius {
+    type Direction = Direction;
+
+    const INITIAL_DIRECTION: Direction = Direction::Up;
+
+    const DIRECTIONS: &'static [Direction] = &SQUARE_DIRECTIONS;
+
+    fn neighbour(&self, point: Point, direction: Direction, w: usize, h: usize) -> Option<Point> {
+        let (x, y) = square_step(point, direction);
+        let y = bounded(y, h)?;
+        let (x, y) = twisted(x, y, w, h);
+        Some(Point { x, y })
+    }
+
+    fn is_opposite(a: Direction, b: Direction) -> bool {
+        !a.is_compatible_with(b)
+    }
+}
+
+impl Topology for Klein {
+    type Direction = Direction;
+
+    const INITIAL_DIRECTION: Direction = Direction::Up;
+
+    const DIRECTIONS: &'static [Direction] = &SQUARE_DIRECTIONS;
+
+    fn neighbour(&self, point: Point, direction: Direction, w: usize, h: usize) -> Option<Point> {
+        let (x, y) = square_step(point, direction);
+        let y = wrap(y, h);
+        let (x, y) = twisted(x, y, w, h);
+        Some(Point { x, y })
+    }
+
+    fn is_opposite(a: Direction, b: Direction) -> bool {
+        !a.is_compatible_with(b)
+    }
+}
+
+impl Topology for Hex {
+    type Direction = HexDirection;
+
+    const INITIAL_DIRECTION: HexDirection = HexDirection::UpRight;
+
+    const DIRECTIONS: &'static [HexDirection] = &[
+        HexDirection::UpLeft,
+        HexDirection::UpRight,
+        HexDirection::Left,
+        HexDirection::Right,
+        HexDirection::DownLeft,
+        HexDirection::DownRight,
+    ];
+
+    const HEXAGONAL: bool = true;
+
+    fn check_size(_width: usize, height: usize) -> Result<(), String> {
+        // Otherwise the top row would wrap onto a row shifted the same way
+        if height % 2 == 1 {
+            return Err(format!("A hex board needs an even height, not {}", height));
+        }
+        Ok(())
+    }
+
+    fn neighbour(
+        &self,
+        point: Point,
+        direction: HexDirection,
+        w: usize,
+        h: usize,
+    ) -> Option<Point> {
+        let (x, y) = (point.x as isize, point.y as isize);
+        // Odd rows are shifted to the right
+        let shift = (point.y % 2) as isize;
+        let (x, y) = match direction {
+            HexDirection::UpLeft => (x - 1 + shift, y + 1),
+            HexDirection::UpRight => (x + shift, y + 1),
+            HexDirection::Left => (x - 1, y),
+            HexDirection::Right => (x + 1, y),
+            HexDirection::DownLeft => (x - 1 + shift, y - 1),
+            HexDirection::DownRight => (x + shift, y - 1),
+        };
+        Some(Point {
+            x: wrap(x, w),
+            y: wrap(y, h),
+        })
+    }
+
+    fn is_opposite(a: HexDirection, b: HexDirection) -> bool {
+        matches!(
+            (a, b),
+            (HexDirection::UpLeft, HexDirection::DownRight)
+                | (HexDirection::DownRight, HexDirection::UpLeft)
+                | (HexDirection::UpRight, HexDirection::DownLeft)
+                | (HexDirection::DownLeft, HexDirection::UpRight)
+                | (HexDirection::Left, HexDirection::Right)
+                | (HexDirection::Right, HexDirection::Left)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Direction, Game, Point};
+
+    use super::{Bounded, Hex, HexDirection, Klein, Mobius, Topology, Torus};
+
+    const W: usize = 10;
+    const H: usize = 6;
+
+    fn p(x: usize, y: usize) -> Point {
+        Point { x, y }
+    }
+
+    #[test]
+    fn test_square() {
+        let cases = [
+            (
+                Torus.neighbour(p(0, 3), Direction::Left, W, H),
+                Some(p(9, 3)),
+            ),
+            (Torus.neighbour(p(4, 5), Direction::Up, W, H), Some(p(4, 0))),
+            (Bounded.neighbour(p(0, 3), Direction::Left, W, H), None),
+            (Bounded.neighbour(p(4, 5), Direction::Up, W, H), None),
+            (
+                Bounded.neighbour(p(4, 4), Direction::Up, W, H),
+                Some(p(4, 5)),
+            ),
+            (
+                Mobius.neighbour(p(0, 1), Direction::Left, W, H),
+                Some(p(9, 4)),
+            ),
+            (
+                Mobius.neighbour(p(9, 0), Direction::Right, W, H),
+                Some(p(0, 5)),
+            ),
+            (Mobius.neighbour(p(4, 5), Direction::Up, W, H), None),
+            (
+                Klein.neighbour(p(0, 1), Direction::Left, W, H),
+                Some(p(9, 4)),
+            ),
+            (Klein.neighbour(p(4, 5), Direction::Up, W, H), Some(p(4, 0))),
+            (
+                Klein.neighbour(p(4, 0), Direction::Down, W, H),
+                Some(p(4, 5)),
+            ),
+        ];
+
+        for (i, (actual, expected)) in cases.into_iter().enumerate() {
+            assert_eq!(actual, expected, "case {}", i);
+        }
+    }
+
+    #[test]
+    fn test_hex() {
+        let cases = [
+            // Even row
+            (p(4, 2), HexDirection::UpLeft, p(3, 3)),
+            (p(4, 2), HexDirection::UpRight, p(4, 3)),
+            (p(4, 2), HexDirection::DownLeft, p(3, 1)),
+            (p(4, 2), HexDirection::DownRight, p(4, 1)),
+            // Odd row
+            (p(4, 3), HexDirection::UpLeft, p(4, 4)),
+            (p(4, 3), HexDirection::UpRight, p(5, 4)),
+            (p(4, 3), HexDirection::DownLeft, p(4, 2)),
+            (p(4, 3), HexDirection::DownRight, p(5, 2)),
+            // Wrap
+            (p(0, 0), HexDirection::DownLeft, p(9, 5)),
+            (p(9, 5), HexDirection::UpRight, p(0, 0)),
+        ];
+
+        for (from, direction, to) in cases {
+            assert_eq!(Hex.neighbour(from, direction, W, H), Some(to));
+        }
+
+        // Going back and forth lands on the same cell
+        for (a, b) in [
+            (HexDirection::UpLeft, HexDirection::DownRight),
+            (HexDirection::UpRight, HexDirection::DownLeft),
+            (HexDirection::Left, HexDirection::Right),
+        ] {
+            assert!(Hex::is_opposite(a, b));
+            for start in [p(4, 2), p(4, 3), p(0, 0), p(9, 5)] {
+                let there = Hex.neighbour(start, a, W, H).unwrap();
+                assert_eq!(Hex.neighbour(there, b, W, H), Some(start));
+            }
+        }
+    }
+
+    #[test]
+    fn test_game_bounded() {
+        let board = "          \n          \n    f     \n    h     \n    b     \n          ";
+        let mut game: Game<Bounded> = board.parse().unwrap();
+
+        game.tick(Direction::Left);
+        game.tick(Direction::Left);
+        game.tick(Direction::Left);
+        game.tick(Direction::Left);
+        assert!(game.last_snapshot().get_game_over_reason().is_none());
+        assert_eq!(game.last_snapshot().snake[0], p(0, 2));
+
+        // Off the board
+        game.tick(Direction::Left);
+        let snapshot = game.last_snapshot();
+        assert!(snapshot.on_wall);
+        assert_eq!(snapshot.snake[0], p(0, 2));
+    }
+
+    #[test]
+    fn test_game_hex() {
+        let board = "          \n    f     \n          \n    h     \n    b     \n          ";
+        let mut game: Game<Hex> = board.parse().unwrap();
+
+        assert_eq!(
+            board[11..].parse::<Game<Hex>>().err().unwrap(),
+            "A hex board needs an even height, not 5"
+        );
+
+        // Cannot turn back
+        game.tick(HexDirection::DownLeft);
+        assert_eq!(game.last_snapshot().snake[0], p(4, 3));
+        game.tick(HexDirection::UpLeft);
+        assert_eq!(game.last_snapshot().snake[0], p(4, 4));
+        assert!(game.last_snapshot().on_food);
+    }
+}
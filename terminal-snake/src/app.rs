@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use ratatui::crossterm::event::KeyCode;
+use snake::{campaign::Campaign, Direction, Game};
+
+pub enum Screen {
+    ChooseLevel {
+        selected: usize,
+    },
+    Play {
+        level: usize,
+        game: Box<Game>,
+    },
+    GameOver {
+        level: usize,
+        score: usize,
+        reason: &'static str,
+    },
+}
+
+/// Everything but the terminal: what is shown and how keys change it
+pub struct App {
+    pub campaign: Campaign,
+    pub screen: Screen,
+    pub quit: bool,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self {
+            campaign: Campaign::default(),
+            screen: Screen::ChooseLevel { selected: 0 },
+            quit: false,
+        }
+    }
+}
+
+fn key_to_direction(code: KeyCode) -> Option<Direction> {
+    match code {
+        KeyCode::Up | KeyCode::Char('w') => Some(Direction::Up),
+        KeyCode::Down | KeyCode::Char('s') => Some(Direction::Down),
+        KeyCode::Left | KeyCode::Char('a') => Some(Direction::Left),
+        KeyCode::Right | KeyCode::Char('d') => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+impl App {
+    pub fn handle_key(&mut self, code: KeyCode) {
+        if matches!(code, KeyCode::Esc | KeyCode::Char('q')) {
+            self.quit = true;
+            return;
+        }
+
+        match &mut self.screen {
+            Screen::ChooseLevel { selected } => match code {
+                KeyCode::Up | KeyCode::Char('w') => *selected = selected.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('s') => {
+                    *selected = (*selected + 1).min(self.campaign.levels().len() - 1)
+                }
+                // Locked levels cannot be played
+                KeyCode::Enter if self.campaign.is_unlocked(*selected) => {
+                    let level = *selected;
+                    self.start(level);
+                }
+                _ => {}
+            },
+            Screen::Play { game, .. } => {
+                if let Some(direction) = key_to_direction(code) {
+                    game.set_direction(direction);
+                }
+            }
+            Screen::GameOver { level, .. } => match code {
+                KeyCode::Char('r') => {
+                    let level = *level;
+                    self.start(level);
+                }
+                KeyCode::Enter => {
+                    self.screen = Screen::ChooseLevel { selected: *level };
+                }
+                _ => {}
+            },
+        }
+    }
+
+    /// Let the game run for `elapsed`
+    pub fn advance(&mut self, elapsed: Duration) {
+        let (level, game) = match &mut self.screen {
+            Screen::Play { level, game } => (*level, game),
+            _ => return,
+        };
+
+        if game.advance(elapsed).is_empty() {
+            return;
+        }
+
+        let snapshot = game.last_snapshot();
+        if let Some(reason) = snapshot.get_game_over_reason() {
+            self.screen = Screen::GameOver {
+                level,
+                score: snapshot.score,
+                reason,
+            };
+            return;
+        }
+
+        // Go to the next level as soon as the goal is reached
+        if self.campaign.record(level, &snapshot) {
+            match self.campaign.next_level(level) {
+                Some(next) => self.start(next),
+                None => {
+                    self.screen = Screen::GameOver {
+                        level,
+                        score: snapshot.score,
+                        reason: "Campaign completed",
+                    }
+                }
+            }
+        }
+    }
+
+    fn start(&mut self, level: usize) {
+        let game: Game = self.campaign.level(level).unwrap().board.parse().unwrap();
+        self.screen = Screen::Play {
+            level,
+            game: Box::new(game),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ratatui::crossterm::event::KeyCode;
+
+    use super::{App, Screen};
+
+    #[test]
+    fn test_app() {
+        let mut app = App::default();
+
+        // The second level is locked
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        assert!(matches!(app.screen, Screen::ChooseLevel { selected: 1 }));
+
+        app.handle_key(KeyCode::Char('w'));
+        app.handle_key(KeyCode::Enter);
+        assert!(matches!(app.screen, Screen::Play { level: 0, .. }));
+
+        // Straight into the wall of snake1
+        app.handle_key(KeyCode::Char('a'));
+        for _ in 0..10 {
+            app.advance(Duration::from_secs(1));
+        }
+        assert!(matches!(
+            app.screen,
+            Screen::GameOver {
+                level: 0,
+                score: 0,
+                reason: "On Wall"
+            }
+        ));
+
+        app.handle_key(KeyCode::Char('r'));
+        assert!(matches!(app.screen, Screen::Play { level: 0, .. }));
+
+        app.handle_key(KeyCode::Esc);
+        assert!(app.quit);
+    }
+}
@@ -0,0 +1,47 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyEventKind},
+    DefaultTerminal,
+};
+
+use app::App;
+
+mod app;
+mod ui;
+
+/// How long to wait for a key before redrawing
+const FRAME: Duration = Duration::from_millis(16);
+
+fn run(terminal: &mut DefaultTerminal) -> io::Result<()> {
+    let mut app = App::default();
+    let mut last = Instant::now();
+
+    while !app.quit {
+        terminal.draw(|frame| ui::render(frame, &app))?;
+
+        if event::poll(FRAME)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key.code);
+                }
+            }
+        }
+
+        let now = Instant::now();
+        app.advance(now - last);
+        last = now;
+    }
+
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal);
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,181 @@
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+    Frame,
+};
+use snake::{topology::Topology, Game};
+
+use crate::app::{App, Screen};
+
+const WALL: char = '#';
+const HEAD: char = '@';
+const BODY: char = 'o';
+const FOOD: char = '*';
+const EMPTY: char = '.';
+const VOID: char = ' ';
+
+/// The board as text, a line per row
+pub fn board_lines<T: Topology>(game: &Game<T>) -> Vec<String> {
+    let (width, height) = game.dim();
+    let mut cells = vec![vec![EMPTY; width]; height];
+
+    let snapshot = game.last_snapshot();
+    // `y` grows upwards, the lines downwards
+    let mut set = |c: char, x: usize, y: usize| {
+        let row = height.checked_sub(y + 1).and_then(|row| cells.get_mut(row));
+        if let Some(cell) = row.and_then(|row| row.get_mut(x)) {
+            *cell = c;
+        }
+    };
+    for p in game.voids() {
+        set(VOID, p.x, p.y);
+    }
+    for p in game.walls() {
+        set(WALL, p.x, p.y);
+    }
+    set(FOOD, snapshot.food_position.x, snapshot.food_position.y);
+    for p in snapshot.snake.iter().skip(1) {
+        set(BODY, p.x, p.y);
+    }
+    if let Some(head) = snapshot.snake.first() {
+        set(HEAD, head.x, head.y);
+    }
+
+    cells
+        .into_iter()
+        .map(|row| row.into_iter().collect())
+        .collect()
+}
+
+fn cell_style(c: char) -> Style {
+    match c {
+        WALL => Style::new().fg(Color::Gray),
+        HEAD => Style::new().fg(Color::Green).bold(),
+        BODY => Style::new().fg(Color::Green),
+        FOOD => Style::new().fg(Color::Red).bold(),
+        _ => Style::new().fg(Color::DarkGray),
+    }
+}
+
+fn board_widget<T: Topology>(game: &Game<T>) -> Paragraph<'static> {
+    let lines: Vec<Line> = board_lines(game)
+        .into_iter()
+        .map(|line| {
+            line.chars()
+                .map(|c| Span::styled(c.to_string(), cell_style(c)))
+                .collect()
+        })
+        .collect();
+    Paragraph::new(lines)
+}
+
+pub fn render(frame: &mut Frame, app: &App) {
+    let [main, help] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+
+    match &app.screen {
+        Screen::ChooseLevel { selected } => {
+            let lines: Vec<Line> = app
+                .campaign
+                .levels()
+                .iter()
+                .enumerate()
+                .map(|(i, level)| {
+                    let cursor = if i == *selected { ">" } else { " " };
+                    let mut line = Line::from(format!("{} {}", cursor, level.name));
+                    if app.campaign.is_completed(i) {
+                        line.push_span(" (completed)");
+                    } else if !app.campaign.is_unlocked(i) {
+                        line = line.dark_gray();
+                        line.push_span(" (locked)");
+                    }
+                    if i == *selected {
+                        line = line.bold();
+                    }
+                    line
+                })
+                .collect();
+            frame.render_widget(
+                Paragraph::new(lines).block(Block::bordered().title("Choose a level")),
+                main,
+            );
+            frame.render_widget(Line::from("↑/↓ select, Enter play, q quit"), help);
+        }
+        Screen::Play { level, game } => {
+            let name = app.campaign.level(*level).map_or("", |l| l.name);
+            let title = format!("{} - score: {}", name, game.last_snapshot().score);
+            frame.render_widget(
+                board_widget(game).block(Block::bordered().title(title)),
+                main,
+            );
+            frame.render_widget(Line::from("arrows/WASD move, q quit"), help);
+        }
+        Screen::GameOver { score, reason, .. } => {
+            let lines = vec![
+                Line::from("Game over").bold(),
+                Line::from(*reason),
+                Line::from(format!("score: {}", score)),
+            ];
+            frame.render_widget(
+                Paragraph::new(lines).block(Block::bordered().title("Game over")),
+                main,
+            );
+            frame.render_widget(Line::from("r retry, Enter choose a level, q quit"), help);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{backend::TestBackend, crossterm::event::KeyCode, Terminal};
+    use snake::{Game, SNAKE_1};
+
+    use super::{board_lines, render};
+    use crate::app::App;
+
+    #[test]
+    fn test_board_lines() {
+        let game: Game = SNAKE_1.parse().unwrap();
+        assert_eq!(
+            board_lines(&game),
+            vec![
+                "##########",
+                "#........#",
+                "#........#",
+                "#...@....#",
+                "#...o.*..#",
+                "##########",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let mut app = App::default();
+        let mut terminal = Terminal::new(TestBackend::new(20, 10)).unwrap();
+
+        terminal.draw(|frame| render(frame, &app)).unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(text.contains("> snake1"));
+        assert!(text.contains("snake2 (locked)"));
+
+        app.handle_key(KeyCode::Enter);
+        terminal.draw(|frame| render(frame, &app)).unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(text.contains("#...@....#"));
+    }
+}